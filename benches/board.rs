@@ -0,0 +1,276 @@
+//! Compares the bitboard against checking the board a cell at a time
+//!
+//! Run with `cargo bench --bench board`. The cell versions are how the board
+//! used to be checked, kept here to measure against.
+
+use std::{collections::VecDeque, hint::black_box, time::{Duration, Instant}};
+
+use jordtris::{board::{self, Board}, movegen, Button, Rotation, RotationSystem, Ruleset, Shape, ShapeColor};
+
+/// How long each benchmark runs for
+const BENCH_TIME: Duration = Duration::from_secs(1);
+
+/// Size of the standard board every benchmark is run on
+const BOARD_WIDTH: usize = 10;
+const BOARD_HEIGHT: usize = 22;
+
+type Cells = [[ShapeColor; BOARD_WIDTH]; BOARD_HEIGHT];
+
+const SHAPES: [Shape; 7] = [Shape::I, Shape::J, Shape::L, Shape::O, Shape::Z, Shape::T, Shape::S];
+const ROTATIONS: [Rotation; 4] = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270];
+
+fn main() {
+    let board = messy_board();
+    let cells: Cells = std::array::from_fn(|y| board[y].try_into().unwrap());
+
+    println!("collision, every piece at every position");
+    let bits = bench("  bitboard", || {
+        let mut fits = 0;
+        for shape in SHAPES {
+            for rotation in ROTATIONS {
+                let mask = board::piece_mask(&shape.get_shape(&rotation));
+                for y in -2..BOARD_HEIGHT as i16 {
+                    for x in -3..BOARD_WIDTH as i16 {
+                        fits += !board.collides(mask, x, y) as u32;
+                    }
+                }
+            }
+        }
+        fits
+    });
+    let cell = bench("  cells", || {
+        let mut fits = 0;
+        for shape in SHAPES {
+            for rotation in ROTATIONS {
+                let shape = shape.get_shape(&rotation);
+                for y in -2..BOARD_HEIGHT as i16 {
+                    for x in -3..BOARD_WIDTH as i16 {
+                        fits += cells_fit(&cells, &shape, x, y) as u32;
+                    }
+                }
+            }
+        }
+        fits
+    });
+    speedup(bits, cell);
+
+    println!("drop, every piece from every column");
+    let bits = bench("  bitboard", || {
+        let mut total = 0;
+        for shape in SHAPES {
+            for rotation in ROTATIONS {
+                let mask = board::piece_mask(&shape.get_shape(&rotation));
+                for x in -3..BOARD_WIDTH as i16 {
+                    if !board.collides(mask, x, 0) {
+                        total += board.drop_y(mask, x, 0);
+                    }
+                }
+            }
+        }
+        total
+    });
+    let cell = bench("  cells", || {
+        let mut total = 0;
+        for shape in SHAPES {
+            for rotation in ROTATIONS {
+                let shape = shape.get_shape(&rotation);
+                for x in -3..BOARD_WIDTH as i16 {
+                    if cells_fit(&cells, &shape, x, 0) {
+                        total += cells_drop(&cells, &shape, x, 0);
+                    }
+                }
+            }
+        }
+        total
+    });
+    speedup(bits, cell);
+
+    println!("line clears, four full rows");
+    let full = full_rows(&board);
+    let full_cells: Cells = std::array::from_fn(|y| full[y].try_into().unwrap());
+    let bits = bench("  bitboard", || full.clone().clear_lines());
+    let cell = bench("  cells", || cells_clear(&mut full_cells.clone()));
+    speedup(bits, cell);
+
+    println!("move generation, every piece from spawn");
+    let rules = Ruleset::default();
+    for shape in SHAPES {
+        // Both have to find the same places for the comparison to mean anything
+        let spawn = rules.spawn(&shape);
+        let found = movegen::placements(&board, rules.rotation_system, shape, Rotation::R0, spawn.clone());
+        assert_eq!(found.len(), cells_placements(&cells, shape, spawn.x, spawn.y).len(), "{}", shape.name());
+    }
+    let bits = bench("  bitboard", || {
+        SHAPES.iter()
+            .map(|shape| movegen::placements(&board, rules.rotation_system, *shape, Rotation::R0, rules.spawn(shape)).len())
+            .sum::<usize>()
+    });
+    let cell = bench("  cells", || {
+        SHAPES.iter()
+            .map(|shape| {
+                let spawn = rules.spawn(shape);
+                cells_placements(&cells, *shape, spawn.x, spawn.y).len()
+            })
+            .sum::<usize>()
+    });
+    speedup(bits, cell);
+}
+
+/// Runs a function over and over, printing and returning the time it took each run
+fn bench<T>(name: &str, mut run: impl FnMut() -> T) -> Duration {
+    // Warm up, then count
+    for _ in 0..100 {
+        black_box(run());
+    }
+
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < BENCH_TIME {
+        black_box(run());
+        runs += 1;
+    }
+
+    let each = start.elapsed() / runs;
+    println!("{name:<12}{:>12.0?}", each);
+    each
+}
+
+fn speedup(bits: Duration, cells: Duration) {
+    println!("  {:.1}x faster", cells.as_secs_f64() / bits.as_secs_f64());
+}
+
+/// Builds a half full board with a few overhangs, like one mid game
+fn messy_board() -> Board {
+    let mut board = Board::default();
+    let mut seed: u32 = 1;
+    for y in 10..BOARD_HEIGHT {
+        for x in 0..BOARD_WIDTH {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            if !(seed >> 16).is_multiple_of(4) {
+                board.set(x, y, ShapeColor::Garbage);
+            }
+        }
+    }
+    board
+}
+
+/// Fills the bottom four rows of a board
+fn full_rows(board: &Board) -> Board {
+    let mut board = board.clone();
+    for y in BOARD_HEIGHT - 4..BOARD_HEIGHT {
+        for x in 0..BOARD_WIDTH {
+            board.set(x, y, ShapeColor::Garbage);
+        }
+    }
+    board
+}
+
+/// Checks a piece against every cell it covers
+fn cells_fit(cells: &Cells, shape: &[[bool; 4]; 4], x: i16, y: i16) -> bool {
+    for (dy, row) in shape.iter().enumerate() {
+        for (dx, filled) in row.iter().enumerate() {
+            if *filled {
+                let (x, y) = (x + dx as i16, y + dy as i16);
+                if !(0..BOARD_WIDTH as i16).contains(&x) || !(0..BOARD_HEIGHT as i16).contains(&y) {
+                    return false;
+                }
+                if cells[y as usize][x as usize].is_block() {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Moves a piece down a row at a time until it lands
+fn cells_drop(cells: &Cells, shape: &[[bool; 4]; 4], x: i16, mut y: i16) -> i16 {
+    while cells_fit(cells, shape, x, y + 1) {
+        y += 1;
+    }
+    y
+}
+
+/// Looks along every row for gaps, moving the board down over each full one
+fn cells_clear(cells: &mut Cells) -> u32 {
+    let mut cleared = 0;
+    let mut y = BOARD_HEIGHT - 1;
+    while y >= 1 {
+        if cells[y].iter().any(|cell| !cell.is_block()) {
+            y -= 1;
+            continue;
+        }
+        for row in (1..=y).rev() {
+            cells[row] = cells[row - 1];
+        }
+        cleared += 1;
+    }
+    cleared
+}
+
+/// Searches for every resting place the way `movegen::placements` does, a cell at a time
+///
+/// Kicks are tracked for the T as they are there, but spins aren't classified.
+fn cells_placements(cells: &Cells, shape: Shape, x: i16, y: i16) -> Vec<(i16, i16, Rotation, Vec<Button>)> {
+    type State = (i16, i16, Rotation, Option<usize>);
+    let boxes = ROTATIONS.map(|rotation| shape.get_shape(&rotation));
+    let fits = |rotation: Rotation, x: i16, y: i16| cells_fit(cells, &boxes[rotation as usize], x, y);
+    let (columns, rows) = (BOARD_WIDTH + 3, BOARD_HEIGHT + 3);
+    let kick_slots = if shape == Shape::T { 6 } else { 1 };
+    let index = |(x, y, rotation, kick): State| {
+        let layer = rotation as usize * kick_slots + kick.map_or(0, |kick| kick + 1);
+        (layer * rows + (y + 3) as usize) * columns + (x + 3) as usize
+    };
+
+    let start = (x, y, Rotation::R0, None);
+    let mut paths: Vec<Option<(Option<State>, Button)>> = vec![None; 4 * kick_slots * rows * columns];
+    paths[index(start)] = Some((None, Button::HardDrop));
+    let mut queue = VecDeque::from([start]);
+    let mut rested = vec![false; paths.len()];
+    let mut found = vec![];
+
+    while let Some(state) = queue.pop_front() {
+        let (x, y, rotation, _) = state;
+        let drop_y = cells_drop(cells, &boxes[rotation as usize], x, y);
+        if !rested[index((x, drop_y, rotation, None))] {
+            rested[index((x, drop_y, rotation, None))] = true;
+            let mut buttons = vec![Button::HardDrop];
+            let mut at = state;
+            while let Some((Some(parent), button)) = paths[index(at)] {
+                buttons.push(button);
+                at = parent;
+            }
+            buttons.reverse();
+            found.push((x, drop_y, rotation, buttons));
+        }
+
+        let mut next = vec![];
+        if fits(rotation, x - 1, y) {
+            next.push(((x - 1, y, rotation, None), Button::Left));
+        }
+        if fits(rotation, x + 1, y) {
+            next.push(((x + 1, y, rotation, None), Button::Right));
+        }
+        if fits(rotation, x, y + 1) {
+            next.push(((x, y + 1, rotation, None), Button::SoftDrop));
+        }
+        for (to, button) in [(rotation.rotate_cw(), Button::RotateCw), (rotation.rotate_ccw(), Button::RotateCcw)] {
+            let kicked = RotationSystem::Srs.kicks(shape, rotation, to).iter()
+                .map(|(dx, dy)| (x + dx, y - dy))
+                .position(|(x, y)| fits(to, x, y));
+            if let Some(kick) = kicked {
+                let (dx, dy) = RotationSystem::Srs.kicks(shape, rotation, to)[kick];
+                next.push(((x + dx, y - dy, to, (shape == Shape::T).then_some(kick)), button));
+            }
+        }
+
+        for (to, button) in next {
+            if paths[index(to)].is_none() {
+                paths[index(to)] = Some((Some(state), button));
+                queue.push_back(to);
+            }
+        }
+    }
+
+    found
+}
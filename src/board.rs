@@ -0,0 +1,182 @@
+use std::{ops::Index, slice};
+
+use crate::{game_state::Coord, rules::BoardSize, shapes::ShapeColor};
+
+/// Wall bits left of the board in each row, as far as a piece's box can hang off
+const LEFT_WALL: usize = 3;
+
+/// Filled rows kept above and below the board, as far as a piece's box can hang off
+const FLOOR: usize = 4;
+
+/// Bits in a row
+const ROW_BITS: usize = u32::BITS as usize;
+
+/// The cells of a piece's box, a row of bits at a time
+///
+/// Made with `piece_mask`, row `dy` of the box is in bits `32 * dy` up, with
+/// bit 0 of each for its left column.
+pub type PieceMask = u128;
+
+/// The cells of a board
+///
+/// Each row is kept as a bitmask of filled columns, walled in either side
+/// and with filled rows above and below, so a piece is checked against the
+/// board in one go. The colour of each cell is kept beside it for drawing.
+/// Rows are indexed from the top and give the colours, writes go through
+/// `set` to keep both in step.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    rows: Vec<u32>,
+    /// Colours of each row from the top, one after the other
+    colors: Vec<ShapeColor>,
+    /// A row with nothing on it, only the walls either side
+    empty_row: u32,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(BoardSize::default())
+    }
+}
+
+impl Board {
+    /// Creates an empty board
+    ///
+    /// Panics if the size isn't valid.
+    pub fn new(size: BoardSize) -> Self {
+        assert!(size.is_valid(), "invalid board size {size:?}");
+        let empty_row = !(((1 << size.width) - 1) << LEFT_WALL);
+        let mut rows = vec![u32::MAX; size.height + 2 * FLOOR];
+        rows[FLOOR..FLOOR + size.height].fill(empty_row);
+        Board {
+            width: size.width,
+            height: size.height,
+            rows,
+            colors: vec![ShapeColor::None; size.width * size.height],
+            empty_row,
+        }
+    }
+
+    /// Gets the number of columns
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the number of rows, including hidden ones
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the filled columns of a row as bits, bit 0 for the leftmost
+    pub fn row(&self, y: usize) -> u32 {
+        (self.rows[FLOOR + y] & !self.empty_row) >> LEFT_WALL
+    }
+
+    /// Gets the colours of every row from the top
+    pub fn iter(&self) -> slice::Chunks<'_, ShapeColor> {
+        self.colors.chunks(self.width)
+    }
+
+    /// Determines if nothing is on the board
+    pub fn is_empty(&self) -> bool {
+        self.rows[FLOOR..FLOOR + self.height].iter().all(|row| *row == self.empty_row)
+    }
+
+    /// Colours a cell, `ShapeColor::None` empties it
+    pub fn set(&mut self, x: usize, y: usize, color: ShapeColor) {
+        assert!(x < self.width, "column {x} is off the board");
+        self.colors[y * self.width + x] = color;
+        let bit = 1 << (LEFT_WALL + x);
+        if color.is_block() {
+            self.rows[FLOOR + y] |= bit;
+        } else {
+            self.rows[FLOOR + y] &= !bit;
+        }
+    }
+
+    /// Determines if a cell is filled, anything outside the board counts as filled
+    pub fn is_filled(&self, x: i16, y: i16) -> bool {
+        if !(0..self.width as i16).contains(&x) || !(0..self.height as i16).contains(&y) {
+            return true;
+        }
+        self.row(y as usize) & (1 << x) != 0
+    }
+
+    /// Determines if a piece's cells fit at a position without overlapping anything
+    pub fn fits(&self, cells: &[[bool; 4]; 4], pos: &Coord) -> bool {
+        !self.collides(piece_mask(cells), pos.x, pos.y)
+    }
+
+    /// Determines if a piece's box overlaps anything with its top left at a position
+    pub fn collides(&self, mask: PieceMask, x: i16, y: i16) -> bool {
+        // Boxes this far out have every cell off the board
+        let left = x + LEFT_WALL as i16;
+        let top = y + FLOOR as i16;
+        if !(0..=(ROW_BITS - 4) as i16).contains(&left) || !(0..=(self.height + FLOOR) as i16).contains(&top) {
+            return true;
+        }
+
+        let top = top as usize;
+        let rows = (0..4).fold(0, |rows, dy| rows | ((self.rows[top + dy] as u128) << (ROW_BITS * dy)));
+        rows & (mask << left) != 0
+    }
+
+    /// Gets the lowest row the top of a piece's box can fall to from a position
+    pub fn drop_y(&self, mask: PieceMask, x: i16, y: i16) -> i16 {
+        let mut drop_y = y;
+        while !self.collides(mask, x, drop_y + 1) {
+            drop_y += 1;
+        }
+        drop_y
+    }
+
+    /// Removes full rows and moves everything above down, returning how many there were
+    pub fn clear_lines(&mut self) -> u32 {
+        // Rows that stay are copied down from the bottom up, leaving one row free a clear
+        let width = self.width;
+        let mut top = self.height;
+        for y in (0..self.height).rev() {
+            if self.rows[FLOOR + y] != u32::MAX {
+                top -= 1;
+                self.rows[FLOOR + top] = self.rows[FLOOR + y];
+                self.colors.copy_within(y * width..(y + 1) * width, top * width);
+            }
+        }
+
+        self.rows[FLOOR..FLOOR + top].fill(self.empty_row);
+        self.colors[..top * width].fill(ShapeColor::None);
+        top as u32
+    }
+
+    /// Pushes every row up by one and adds a row at the bottom, the top row is lost
+    pub fn push_up(&mut self, row: &[ShapeColor]) {
+        self.rows.copy_within(FLOOR + 1..FLOOR + self.height, FLOOR);
+        self.colors.copy_within(self.width.., 0);
+        for (x, color) in row.iter().enumerate() {
+            self.set(x, self.height - 1, *color);
+        }
+    }
+}
+
+impl Index<usize> for Board {
+    type Output = [ShapeColor];
+
+    fn index(&self, y: usize) -> &Self::Output {
+        &self.colors[y * self.width..(y + 1) * self.width]
+    }
+}
+
+/// Packs the cells of a piece's box for checking against a board
+pub fn piece_mask(cells: &[[bool; 4]; 4]) -> PieceMask {
+    let mut mask = 0;
+    for (dy, row) in cells.iter().enumerate() {
+        for (dx, filled) in row.iter().enumerate() {
+            if *filled {
+                mask |= 1 << (ROW_BITS * dy + dx);
+            }
+        }
+    }
+    mask
+}
@@ -0,0 +1,208 @@
+use crate::{
+    board::Board,
+    game_state::{Button, Coord, GameState, Input, TICKS_PER_SECOND},
+    movegen::{placements, Placement},
+    rotation_system::RotationSystem,
+    scoring::TSpin,
+    shapes::{Rotation, Shape},
+};
+
+/// How much each feature of a board is worth to the bot, most are penalties
+#[derive(Clone, PartialEq, Debug)]
+pub struct Weights {
+    /// Per column, summed
+    pub height: f64,
+    /// Tallest column, on top of the sum
+    pub max_height: f64,
+    /// Empty cells with a filled cell somewhere above them
+    pub holes: f64,
+    /// Height differences between neighbouring columns
+    pub bumpiness: f64,
+    /// Depth of columns lower than both neighbours, past the deepest one
+    pub wells: f64,
+    /// Slots a T piece could spin into for a double
+    pub tslots: f64,
+    /// Indexed by lines cleared
+    pub clears: [f64; 5],
+    /// Indexed by lines cleared, on top of the clear
+    pub tspins: [f64; 4],
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            height: -0.5,
+            max_height: -1.0,
+            holes: -8.0,
+            bumpiness: -0.6,
+            wells: -0.8,
+            tslots: 3.0,
+            clears: [0.0, -2.0, -1.0, 1.0, 12.0],
+            tspins: [1.0, 6.0, 14.0, 18.0],
+        }
+    }
+}
+
+/// A player built in to the engine
+///
+/// Searches every placement of the current and hold piece, scores the
+/// boards they leave and plays the best one. Each piece is played in a single
+/// tick, with the wait between pieces setting the speed.
+#[derive(Clone, Debug)]
+pub struct Bot {
+    pub weights: Weights,
+    /// Ticks between pieces
+    pub delay: u64,
+    /// Tick the next piece may be played on
+    next_move: u64,
+}
+
+impl Bot {
+    /// Creates a bot that places up to a number of pieces a second
+    pub fn new(pps: f64) -> Self {
+        Bot {
+            weights: Weights::default(),
+            delay: (TICKS_PER_SECOND as f64 / pps).round() as u64,
+            next_move: 0,
+        }
+    }
+
+    /// Forgets the game being played, ready for a new one
+    pub fn reset(&mut self) {
+        self.next_move = 0;
+    }
+
+    /// Gets the inputs for the game's next tick
+    pub fn think(&mut self, game: &GameState) -> Vec<Input> {
+        if game.tick < self.next_move {
+            return vec![];
+        }
+        self.next_move = game.tick + self.delay.max(1);
+
+        let mut buttons = match self.best_move(game) {
+            Some(buttons) => buttons,
+            None => vec![Button::HardDrop], // Nothing fits, the game is lost anyway
+        };
+
+        // Letting go straight away stops anything auto shifting
+        buttons.drain(..).flat_map(|button| [Input::Press(button), Input::Release(button)]).collect()
+    }
+
+    /// Finds the buttons that play the best placement, holding first if that is better
+    pub fn best_move(&self, game: &GameState) -> Option<Vec<Button>> {
+        let mut best: Option<(f64, Vec<Button>)> = None;
+
+        // The current piece from where it is now
+        for placement in game.placements() {
+            let score = self.score(&game.board, game.rules.rotation_system, game.current_shape, &placement);
+            if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                best = Some((score, placement.buttons));
+            }
+        }
+
+        // The held or next piece from spawn
+        let swap = game.held.or(game.shape_queue.first().copied());
+        if let Some(shape) = swap.filter(|_| !game.just_held) {
+            for placement in placements(&game.board, game.rules.rotation_system, shape, Rotation::R0, game.rules.spawn(&shape)) {
+                let score = self.score(&game.board, game.rules.rotation_system, shape, &placement);
+                if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                    let buttons = [Button::Hold].into_iter().chain(placement.buttons).collect();
+                    best = Some((score, buttons));
+                }
+            }
+        }
+
+        best.map(|(_, buttons)| buttons)
+    }
+
+    /// Scores the board a placement leaves, higher is better
+    fn score(&self, board: &Board, rotation_system: RotationSystem, shape: Shape, placement: &Placement) -> f64 {
+        let mut board = board.clone();
+        let box_cells = rotation_system.cells(shape, placement.rotation);
+        for (x, y) in cells(&box_cells, &placement.pos) {
+            board.set(x as usize, y as usize, shape.get_color());
+        }
+        let lines = board.clear_lines() as usize;
+
+        let w = &self.weights;
+        let mut score = w.clears[lines.min(4)] + self.evaluate(&board);
+        if placement.spin != TSpin::None {
+            score += w.tspins[lines.min(3)];
+        }
+        score
+    }
+
+    /// Scores the shape of a board, higher is better
+    fn evaluate(&self, board: &Board) -> f64 {
+        let w = &self.weights;
+        let (width, height) = (board.width(), board.height());
+        let heights = heights(board);
+
+        let mut holes = 0;
+        for (x, column_height) in heights.iter().enumerate() {
+            let top = height - column_height;
+            holes += (top..height).filter(|y| !board.is_filled(x as i16, *y as i16)).count();
+        }
+
+        let bumpiness: usize = heights.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum();
+
+        // The deepest well is kept for tetrises, the rest are trouble
+        let mut wells: Vec<usize> = (0..width)
+            .map(|x| {
+                let left = if x == 0 { height } else { heights[x - 1] };
+                let right = if x == width - 1 { height } else { heights[x + 1] };
+                left.min(right).saturating_sub(heights[x])
+            })
+            .collect();
+        wells.sort_unstable();
+        let wells: usize = wells[..width - 1].iter().sum();
+
+        w.height * heights.iter().sum::<usize>() as f64
+            + w.max_height * *heights.iter().max().unwrap() as f64
+            + w.holes * holes as f64
+            + w.bumpiness * bumpiness as f64
+            + w.wells * wells as f64
+            + w.tslots * tslots(board) as f64
+    }
+}
+
+/// Gets the board cells a piece covers
+fn cells(box_cells: &[[bool; 4]; 4], pos: &Coord) -> Vec<(i16, i16)> {
+    let mut cells = vec![];
+    for (dy, row) in box_cells.iter().enumerate() {
+        for (dx, filled) in row.iter().enumerate() {
+            if *filled {
+                cells.push((pos.x + dx as i16, pos.y + dy as i16));
+            }
+        }
+    }
+    cells
+}
+
+/// Gets the height of each column, counting up to its top filled cell
+fn heights(board: &Board) -> Vec<usize> {
+    let height = board.height();
+    (0..board.width())
+        .map(|x| (0..height).find(|y| board.row(*y) & (1 << x) != 0).map_or(0, |top| height - top))
+        .collect()
+}
+
+/// Counts slots a T piece could spin down into for a double
+///
+/// A slot is an upside down T of empty cells with both bottom corners
+/// filled and an overhang over one side of the top.
+fn tslots(board: &Board) -> usize {
+    let filled = |x: usize, y: usize| board.row(y) & (1 << x) != 0;
+    let mut slots = 0;
+    for y in 1..board.height() - 1 {
+        for x in 0..board.width() - 2 {
+            let open = !filled(x, y) && !filled(x + 1, y) && !filled(x + 2, y) && !filled(x + 1, y + 1);
+            let corners = filled(x, y + 1) && filled(x + 2, y + 1);
+            let overhang = filled(x, y - 1) || filled(x + 2, y - 1);
+            if open && corners && overhang && !filled(x + 1, y - 1) {
+                slots += 1;
+            }
+        }
+    }
+    slots
+}
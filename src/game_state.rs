@@ -1,17 +1,79 @@
-use std::time::Instant;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use crate::{shapes::{Rotation, Shape, ShapeColor}, Direction};
+use crate::{board::{self, Board}, movegen::{self, Placement}, randomizer::PieceGenerator, rules::{GameMode, LockMode, Ruleset, MAX_GRAVITY, ONE_G}, scoring::{self, ClearScore, GameEvent, TSpin}, shapes::{Rotation, Shape, ShapeColor}};
 
 /// A position on the screen
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Coord {
     pub x: i16,
     pub y: i16,
 }
 
+/// A direction
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Left,
+    Right,
+    Up, 
+    Down,
+}
+
+impl Direction {
+    // Returns a value of 1 or -1 based on the direction
+    pub fn to_value(&self) -> i16 {
+        match self {
+            Direction::Left => -1,
+            Direction::Right => 1,
+            Direction::Up => -1,
+            Direction::Down => 1,
+        }
+    }
+}
+
+/// Number of simulation ticks in one second of play
+pub const TICKS_PER_SECOND: u32 = 60;
+
+/// Lines between recorded splits
+pub const SPLIT_LINES: u32 = 10;
+
+/// Garbage rows kept on the board while digging
+pub const DIG_ROWS: u32 = 10;
+
+/// Upcoming pieces kept in the queue
+pub const QUEUE_LENGTH: usize = 7;
+
+/// Mixed into the seed for the garbage generator, so garbage doesn't change the pieces
+const GARBAGE_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A button the player can press
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    Left,
+    Right,
+    RotateCw,
+    RotateCcw,
+    SoftDrop,
+    HardDrop,
+    Hold,
+}
+
+/// Something from outside the engine fed into it on a tick
+///
+/// Front ends that can't detect key releases should send a release straight
+/// after every press, the press alone still moves or drops by one cell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    Press(Button),
+    Release(Button),
+    /// Lines of garbage sent by an opponent
+    Garbage(u32),
+}
+
 /// Represent the current phase of the game the player is in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GamePhase {
     Playing,
+    Paused,
     GameOver,
     Help,
     Score,
@@ -22,67 +84,364 @@ pub struct GameState {
     pub player_pos: Coord,
     pub current_shape: Shape,
     pub rotation: Rotation,
-    pub board: [[ShapeColor; 10]; 22],
-    pub last_fall: Instant,
-    pub last_input: Instant,
-    pub score: i32,
+    pub board: Board,
+    pub rules: Ruleset,
+    pub tick: u64,
+    /// Ticks the player has spent grounded since the timer last reset
+    pub lock_timer: u32,
+    /// Lock timer resets used since the player last reached a new lowest row
+    pub move_resets: u32,
+    /// Lowest row the top of the player has reached
+    pub lowest_row: i16,
+    pub left_held: bool,
+    pub right_held: bool,
+    pub soft_drop_held: bool,
+    /// Direction being auto shifted, the most recently pressed held direction
+    pub shift: Option<Direction>,
+    /// Ticks the shift direction has been held for
+    pub shift_timer: u32,
+    /// Gravity built up towards the next row, in units of `ONE_G`
+    pub fall_progress: u32,
+    pub score: u32,
+    pub level: u32,
+    pub lines: u32,
+    /// Pieces locked so far
+    pub pieces: u32,
+    /// Tick each multiple of `SPLIT_LINES` lines was reached on
+    pub splits: Vec<u64>,
+    /// Set when the game ended by reaching the mode's goal rather than topping out
+    pub completed: bool,
+    /// Consecutive clears so far, none if the last piece cleared nothing
+    pub combo: Option<u32>,
+    /// Set when the last clear was difficult, so the next one is back to back
+    pub back_to_back: bool,
+    /// Events since the front end last called `take_events`
+    pub events: Vec<GameEvent>,
+    /// Every input applied so far with its tick, for replays
+    pub input_log: Vec<(u64, Input)>,
     pub held: Option<Shape>,
     pub shape_queue: Vec<Shape>,
     pub just_held: bool,
+    /// Kick index used if the last successful action was a rotation
+    pub last_kick: Option<usize>,
+    /// Garbage rows still to be added in dig mode
+    pub garbage_left: u32,
+    /// Column of the hole in the last garbage row added
+    pub garbage_hole: Option<usize>,
+    /// Marks the board rows, from the top, that are dig mode garbage rather than received
+    dig_rows: Vec<bool>,
+    /// Garbage received from opponents, as lines and the tick they can rise on
+    pub pending_garbage: Vec<(u32, u64)>,
     pub game_phase: GamePhase,
+    pub seed: u64,
+    generator: PieceGenerator,
+    garbage_rng: StdRng,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameState {
-    /// Creates a new game
+    /// Creates a new game with a random seed
     pub fn new() -> Self {
-        let shape = Shape::random(); // Get starting shape
-        GameState {
-            player_pos: shape.get_spawn_offsets(),
+        Self::with_seed(rand::random())
+    }
+
+    /// Creates a new game whose piece sequence is fully determined by the seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rules(seed, Ruleset::default())
+    }
+
+    /// Creates a new seeded game played with the given rules
+    pub fn with_rules(seed: u64, rules: Ruleset) -> Self {
+        let mut generator = PieceGenerator::new(rules.randomizer, seed);
+        let shape = generator.next_shape(); // Get starting shape
+        let shape_queue = (0..QUEUE_LENGTH).map(|_| generator.next_shape()).collect();
+        let garbage_left = match rules.mode {
+            GameMode::Dig { lines, .. } => lines,
+            _ => 0,
+        };
+        let spawn = rules.spawn(&shape);
+        let dig_rows = vec![false; rules.board.height];
+        let mut game = GameState {
+            player_pos: spawn.clone(),
             current_shape: shape,
             rotation: Rotation::R0,
-            board: [[ShapeColor::None; 10]; 22],
-            last_fall: Instant::now(),
-            last_input: Instant::now(),
+            board: Board::new(rules.board),
+            level: rules.start_level.max(1),
+            rules,
+            tick: 0,
+            lock_timer: 0,
+            move_resets: 0,
+            lowest_row: spawn.y,
+            left_held: false,
+            right_held: false,
+            soft_drop_held: false,
+            shift: None,
+            shift_timer: 0,
+            fall_progress: 0,
             score: 0,
+            lines: 0,
+            pieces: 0,
+            splits: vec![],
+            completed: false,
+            combo: None,
+            back_to_back: false,
+            events: vec![],
+            input_log: vec![],
             held: None,
-            shape_queue: create_new_7_bag().to_vec(),
+            shape_queue,
             just_held: false,
+            last_kick: None,
+            garbage_left,
+            garbage_hole: None,
+            dig_rows,
+            pending_garbage: vec![],
             game_phase: GamePhase::Playing,
+            seed,
+            generator,
+            garbage_rng: StdRng::seed_from_u64(seed ^ GARBAGE_SEED),
+        };
+
+        // Dig mode starts with a board full of garbage
+        game.refill_garbage();
+        game
+    }
+
+    /// Advances the game by one tick, applying the given inputs first
+    ///
+    /// Does nothing unless the game is being played.
+    pub fn step(&mut self, inputs: &[Input]) {
+        if self.game_phase != GamePhase::Playing {
+            return;
+        }
+
+        // Apply player inputs
+        for input in inputs {
+            self.input_log.push((self.tick, *input));
+            match *input {
+                Input::Press(button) => self.press(button),
+                Input::Release(button) => self.release(button),
+                Input::Garbage(lines) => self.receive_garbage(lines),
+            }
+        }
+
+        // An input that ended the game leaves the board as it is, the tick still counts
+        if self.game_phase != GamePhase::Playing {
+            self.tick += 1;
+            return;
+        }
+
+        // Held directions auto shift
+        self.auto_shift();
+
+        // Apply gravity, a row at a time
+        let gravity = self.rules.gravity.for_level(self.level);
+        let soft_drop = self.soft_drop_held;
+        self.fall_progress += match self.rules.handling.soft_drop_factor {
+            Some(factor) if soft_drop => gravity.saturating_mul(factor).min(MAX_GRAVITY),
+            None if soft_drop => MAX_GRAVITY, // Infinite soft drop
+            _ => gravity,
+        };
+        while self.fall_progress >= ONE_G {
+            self.fall_progress -= ONE_G;
+            if !self.fall_player() {
+                self.fall_progress = 0; // Grounded, don't build up gravity
+                break;
+            }
+
+            // Rows gained by soft dropping score
+            if soft_drop {
+                self.award_drop(false, 1);
+            }
+        }
+
+        // Lock a grounded piece once its timer runs out
+        self.update_lock();
+
+        self.tick += 1;
+
+        // Timed modes end when the clock runs out
+        if self.game_phase == GamePhase::Playing && self.goal_reached() {
+            self.complete();
         }
     }
 
-    /// Determines if a piece can be placed at a position
-    pub fn can_place(&self, shape: &Shape, rot: &Rotation, at: &Coord) -> bool {
-        let shape = shape.get_shape(rot);
-        //let mut right_most = 0;
-
-        // Iterate shape
-        for dx in 0..4 {
-            for dy in 0..4 {
-                if shape[dy][dx] { // Shape tile found
-                    // Get new coords
-                    let x = at.x + dx as i16;
-                    let y = at.y + dy as i16;
-
-                    // Bounds checks
-                    if x < 0 || y < 0 {
-                        return false;
-                    }
-
-                    if x >= 10 || y >= 22 {
-                        return false;
-                    }
-
-                    // Check for existing tile
-                    if y >= 0 && self.board[y as usize][x as usize].is_block() { // Safe cast, bound check
-                        return false;
-                    }
+    /// Handles a button being pressed down
+    fn press(&mut self, button: Button) {
+        match button {
+            Button::Left => {
+                self.left_held = true;
+                self.start_shift(Direction::Left);
+            },
+            Button::Right => {
+                self.right_held = true;
+                self.start_shift(Direction::Right);
+            },
+            Button::SoftDrop => {
+                self.soft_drop_held = true;
+                if self.fall_player() {
+                    self.award_drop(false, 1);
                 }
+            },
+            Button::RotateCw => self.rotate_player(Direction::Up),
+            Button::RotateCcw => self.rotate_player(Direction::Down),
+            Button::HardDrop => self.hard_drop(),
+            Button::Hold => self.hold(),
+        }
+    }
+
+    /// Moves once in a newly pressed direction, which takes over auto shifting
+    fn start_shift(&mut self, dir: Direction) {
+        self.shift = Some(dir);
+        self.shift_timer = 0;
+        self.move_player_horizontal(dir);
+    }
+
+    /// Handles a button being let go
+    fn release(&mut self, button: Button) {
+        match button {
+            Button::Left => self.left_held = false,
+            Button::Right => self.right_held = false,
+            Button::SoftDrop => self.soft_drop_held = false,
+            _ => return,
+        }
+
+        // Fall back to the other direction if it is still held
+        let other = match self.shift {
+            Some(Direction::Left) if !self.left_held => self.right_held.then_some(Direction::Right),
+            Some(Direction::Right) if !self.right_held => self.left_held.then_some(Direction::Left),
+            _ => return,
+        };
+        self.shift = other;
+        self.shift_timer = 0;
+    }
+
+    /// Repeats the held direction once the delayed auto shift has charged
+    fn auto_shift(&mut self) {
+        let Some(dir) = self.shift else {
+            return;
+        };
+
+        // Count this tick, repeats start das ticks after the press
+        let handling = self.rules.handling;
+        let held = self.shift_timer;
+        self.shift_timer = held.saturating_add(1);
+        if held < handling.das {
+            return;
+        }
+
+        if handling.arr == 0 {
+            // Instant repeat slides all the way
+            while self.move_player_horizontal(dir) {}
+        } else if (held - handling.das).is_multiple_of(handling.arr) {
+            self.move_player_horizontal(dir);
+        }
+    }
+
+    /// Advances the lock timer, locking the player when it expires
+    fn update_lock(&mut self) {
+        if !self.is_grounded() {
+            // Classic timers keep running once started
+            if self.rules.lock_mode != LockMode::Classic {
+                self.lock_timer = 0;
             }
+            return;
         }
 
-        // All cases passed
-        true
+        self.lock_timer += 1;
+
+        // Out of resets locks as soon as the piece is grounded
+        let out_of_resets = match self.rules.lock_mode {
+            LockMode::Extended { max_resets } => self.move_resets >= max_resets,
+            _ => false,
+        };
+
+        if out_of_resets || self.lock_timer >= self.rules.lock_delay {
+            self.place_and_reset();
+        }
+    }
+
+    /// Resets the lock timer after a successful move or rotation, if the lock mode allows
+    fn on_player_moved(&mut self) {
+        match self.rules.lock_mode {
+            LockMode::Extended { max_resets } => {
+                if self.move_resets < max_resets {
+                    self.move_resets += 1;
+                    self.lock_timer = 0;
+                }
+            },
+            LockMode::Infinite => self.lock_timer = 0,
+            LockMode::StepReset | LockMode::Classic => {},
+        }
+    }
+
+    /// Resets the lock timer after the player moves down a row, if the lock mode allows
+    fn on_player_fell(&mut self) {
+        match self.rules.lock_mode {
+            LockMode::Extended { .. } => {
+                // Only a new lowest row restores resets
+                if self.player_pos.y > self.lowest_row {
+                    self.lowest_row = self.player_pos.y;
+                    self.move_resets = 0;
+                    self.lock_timer = 0;
+                }
+            },
+            LockMode::Infinite | LockMode::StepReset => self.lock_timer = 0,
+            LockMode::Classic => {},
+        }
+    }
+
+    /// Pauses a game being played, freezing every timer
+    pub fn pause(&mut self) {
+        if self.game_phase != GamePhase::Playing {
+            return;
+        }
+        self.game_phase = GamePhase::Paused;
+
+        // Releases can't be seen while paused, so let go of everything, logged
+        // so replays let go too
+        let held = [(self.left_held, Button::Left), (self.right_held, Button::Right), (self.soft_drop_held, Button::SoftDrop)];
+        for (_, button) in held.into_iter().filter(|(held, _)| *held) {
+            self.input_log.push((self.tick, Input::Release(button)));
+            self.release(button);
+        }
+    }
+
+    /// Resumes a paused game
+    pub fn resume(&mut self) {
+        if let GamePhase::Paused | GamePhase::Help = self.game_phase {
+            self.game_phase = GamePhase::Playing;
+        }
+    }
+
+    /// Determines if the player is resting on the stack or floor
+    pub fn is_grounded(&self) -> bool {
+        let below = Coord { x: self.player_pos.x, y: self.player_pos.y + 1 };
+        !self.can_place(&self.current_shape, &self.rotation, &below)
+    }
+
+    /// Gets every resting place the current piece can reach from where it is
+    pub fn placements(&self) -> Vec<Placement> {
+        movegen::placements(&self.board, self.rules.rotation_system, self.current_shape, self.rotation, self.player_pos.clone())
+    }
+
+    /// Gets the cells of a piece's box in a rotation, under the game's rotation system
+    pub fn shape_cells(&self, shape: Shape, rot: Rotation) -> [[bool; 4]; 4] {
+        self.rules.rotation_system.cells(shape, rot)
+    }
+
+    /// Determines if a piece can be placed at a position
+    pub fn can_place(&self, shape: &Shape, rot: &Rotation, at: &Coord) -> bool {
+        self.board.fits(&self.shape_cells(*shape, *rot), at)
+    }
+
+    /// Determines if a cell is filled, anything outside the board counts as filled
+    pub fn is_occupied(&self, x: i16, y: i16) -> bool {
+        self.board.is_filled(x, y)
     }
 
     /// Places the player onto the board, triggers game over
@@ -94,31 +453,181 @@ impl GameState {
         // Determine if player can be placed
         if !self.can_place(&shape, &self.rotation, &self.player_pos) {
             self.game_phase = GamePhase::GameOver; // Piece cant be placed, game over
+            return;
         }
 
         // Get shape array
-        let shape = shape.get_shape(&self.rotation);
+        let shape = self.shape_cells(shape, self.rotation);
 
         // Iterate player
-        for dx in 0..4 {
-            for dy in 0..4 {
+        for (dy, row) in shape.iter().enumerate() {
+            for (dx, filled) in row.iter().enumerate() {
                 // Get tiles in shape
-                if shape[dy][dx] {
+                if *filled {
                     // Get board positions
                     let x = (self.player_pos.x + dx as i16) as usize;
                     let y = (self.player_pos.y + dy as i16) as usize;
 
                     // Place piece
-                    self.board[y][x] = self.current_shape.get_color(); 
+                    self.board.set(x, y, self.current_shape.get_color());
                 }
             }
         }
 
         // Allow hold again
         self.just_held = false;
+        self.pieces += 1;
+
+        // Classify before the board changes, then clear any lines
+        let tspin = self.tspin();
+        let lines = self.clear_lines();
+        self.score_clear(lines, tspin);
+
+        // Garbage only rises on pieces that don't clear
+        if lines == 0 {
+            self.raise_garbage();
+        }
+    }
+
+    /// Locks the current piece at a resting position without moving it there
+    ///
+    /// For bots that choose a placement rather than pressing buttons, so it
+    /// isn't recorded in the input log. The rest has to be one of `placements`,
+    /// and spins are classified by how that gets there. Returns false and
+    /// leaves the game as it was if the piece can't reach the rest.
+    pub fn place_at(&mut self, rotation: Rotation, pos: Coord) -> bool {
+        if self.game_phase != GamePhase::Playing {
+            return false;
+        }
+        let Some(placement) = self.placements().into_iter().find(|placement| placement.rotation == rotation && placement.pos == pos) else {
+            return false;
+        };
+
+        self.rotation = rotation;
+        self.player_pos = pos;
+        self.last_kick = placement.kick;
+        self.place_and_reset();
+        true
+    }
+
+    /// Classifies the current T piece placement using the 3 corner rule
+    pub fn tspin(&self) -> TSpin {
+        // Only T pieces that were just rotated can spin
+        let Some(kick) = self.last_kick else {
+            return TSpin::None;
+        };
+        if self.current_shape != Shape::T {
+            return TSpin::None;
+        }
+
+        classify_tspin(&self.board, &self.shape_cells(Shape::T, self.rotation), &self.player_pos, kick)
+    }
+
+    /// Awards points for the lines cleared by the last locked piece
+    fn score_clear(&mut self, lines: u32, tspin: TSpin) {
+        // Placing without clearing breaks the combo
+        if lines == 0 {
+            self.combo = None;
+
+            // T-spins still score without lines
+            if tspin == TSpin::None {
+                return;
+            }
+        }
+
+        // Score clear
+        let combo = if lines > 0 { self.combo.map_or(0, |combo| combo + 1) } else { 0 };
+        let perfect_clear = lines > 0 && self.board.is_empty();
+        let clear = ClearScore::new(lines, tspin, self.level, combo, self.back_to_back, perfect_clear);
+
+        // Update chains, zero line T-spins don't affect back to back
+        if lines > 0 {
+            self.combo = Some(combo);
+            self.back_to_back = scoring::is_difficult(lines, tspin);
+            self.add_lines(lines);
+        }
+
+        self.score += clear.total();
+        self.send_attack(self.rules.attack.attack(&clear));
+        self.events.push(GameEvent::Clear(clear));
+    }
+
+    /// Queues garbage sent by an opponent to rise once the garbage delay has passed
+    fn receive_garbage(&mut self, lines: u32) {
+        if lines > 0 {
+            let ready = self.tick + self.rules.garbage_delay as u64;
+            self.pending_garbage.push((lines, ready));
+        }
+    }
+
+    /// Cancels incoming garbage with an attack, sending whatever is left over
+    fn send_attack(&mut self, mut attack: u32) {
+        // Oldest garbage is cancelled first
+        while attack > 0 && let Some((lines, _)) = self.pending_garbage.first_mut() {
+            let cancelled = attack.min(*lines);
+            attack -= cancelled;
+            *lines -= cancelled;
+            if *lines == 0 {
+                self.pending_garbage.remove(0);
+            }
+        }
+
+        if attack > 0 {
+            self.events.push(GameEvent::Attack(attack));
+        }
+    }
+
+    /// Adds every pending garbage attack that is ready to the board, each with its own hole
+    fn raise_garbage(&mut self) {
+        while let Some((lines, ready)) = self.pending_garbage.first().copied() {
+            if ready > self.tick {
+                break;
+            }
+            self.pending_garbage.remove(0);
+
+            let hole = self.next_garbage_hole(100);
+            for _ in 0..lines {
+                self.add_garbage_row(hole);
+            }
+        }
+    }
+
+    /// Gets the lines of garbage waiting to rise
+    pub fn incoming_garbage(&self) -> u32 {
+        self.pending_garbage.iter().map(|(lines, _)| lines).sum()
+    }
+
+    /// Gets the lines of garbage that will rise when the next piece locks without clearing
+    pub fn ready_garbage(&self) -> u32 {
+        self.pending_garbage.iter()
+            .filter(|(_, ready)| *ready <= self.tick)
+            .map(|(lines, _)| lines)
+            .sum()
+    }
+
+    /// Counts cleared lines towards the next level
+    fn add_lines(&mut self, lines: u32) {
+        self.lines += lines;
+        while (self.splits.len() as u32 + 1) * SPLIT_LINES <= self.lines {
+            self.splits.push(self.tick);
+        }
+
+        let level_ups = self.lines / self.rules.lines_per_level.max(1);
+        self.level = self.rules.start_level.max(1) + level_ups;
+    }
+
+    /// Awards points for dropping the player some number of cells
+    fn award_drop(&mut self, hard: bool, cells: u32) {
+        let per_cell = if hard { scoring::HARD_DROP_POINTS } else { scoring::SOFT_DROP_POINTS };
+        let points = per_cell * cells;
+
+        self.score += points;
+        self.events.push(GameEvent::Drop { hard, cells, points });
+    }
 
-        // Attempt to clear any lines
-        self.clear_lines();
+    /// Removes and returns every event since the last call
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
     }
 
     /// Spawns a random new piece at the top of the board 
@@ -131,6 +640,17 @@ impl GameState {
     /// Places and resets the player
     pub fn place_and_reset(&mut self) {
         self.place_player();
+        if self.game_phase == GamePhase::GameOver {
+            return;
+        }
+        self.refill_garbage();
+
+        // Reaching the goal ends the game before the next piece spawns
+        if self.goal_reached() {
+            self.complete();
+            return;
+        }
+
         self.reset_player_piece();
 
         // Determine if moving player to top is game loss
@@ -139,35 +659,101 @@ impl GameState {
         }
     }
 
-    /// Checks and clears any lines the player has created
-    fn clear_lines(&mut self) {
-        // Iterate vertically
-        let mut y = 21;
-        'outer: while y >= 1 {
-            // Iterate across line
-            for x in 0..10 {
-                // If any tiles are not blocks
-                if !self.board[y][x].is_block() {
-                    y -= 1; // Decrease y
-                    continue 'outer;
-                }
-            }
+    /// Determines if the mode's goal has been met
+    pub fn goal_reached(&self) -> bool {
+        match self.rules.mode {
+            GameMode::Endless => false,
+            GameMode::Sprint { lines } => self.lines >= lines,
+            GameMode::Ultra { .. } => self.ticks_left() == Some(0),
+            GameMode::Dig { .. } => self.garbage_left == 0 && self.garbage_rows() == 0,
+        }
+    }
 
-            // Clear line/move board down
-            for row in (1..=y).rev() {
-                self.board[row] = self.board[row-1];
-            }
+    /// Counts the dig mode garbage rows on the board, received garbage doesn't count
+    pub fn garbage_rows(&self) -> u32 {
+        self.dig_rows.iter().filter(|dig| **dig).count() as u32
+    }
+
+    /// Counts the garbage rows cleared so far in dig mode
+    pub fn garbage_dug(&self) -> u32 {
+        match self.rules.mode {
+            GameMode::Dig { lines, .. } => lines - self.garbage_left - self.garbage_rows(),
+            _ => 0,
+        }
+    }
 
-            // Increase score
-            self.score += 100; // TODO: Proper scoring system
+    /// Tops the board back up to `DIG_ROWS` garbage rows while there are some left to add,
+    /// or half the visible rows on short boards
+    fn refill_garbage(&mut self) {
+        let GameMode::Dig { messiness, .. } = self.rules.mode else {
+            return;
+        };
+
+        let rows = DIG_ROWS.min(self.rules.board.visible_rows() as u32 / 2);
+        while self.garbage_left > 0 && self.garbage_rows() < rows {
+            let hole = self.next_garbage_hole(messiness);
+            self.add_garbage_row(hole);
+            *self.dig_rows.last_mut().unwrap() = true;
+            self.garbage_left -= 1;
         }
     }
 
-    /// Moves the player to the left or right
-    pub fn move_player_horizontal(&mut self, dir: Direction) {
+    /// Picks the hole for the next garbage row, moving it from the last one
+    /// with a percent chance of messiness
+    fn next_garbage_hole(&mut self, messiness: u32) -> usize {
+        let width = self.board.width();
+        let roll = self.garbage_rng.random_range(0..100);
+        let hole = match self.garbage_hole {
+            Some(hole) if roll >= messiness => hole,
+            Some(hole) => (hole + self.garbage_rng.random_range(1..width)) % width,
+            None => self.garbage_rng.random_range(0..width),
+        };
+        self.garbage_hole = Some(hole);
+        hole
+    }
+
+    /// Pushes the board up and fills the bottom row with garbage, leaving a hole
+    pub fn add_garbage_row(&mut self, hole: usize) {
+        // Anything pushed off the top is lost
+        let mut row = vec![ShapeColor::Garbage; self.board.width()];
+        row[hole] = ShapeColor::None;
+        self.board.push_up(&row);
+        self.dig_rows.remove(0);
+        self.dig_rows.push(false);
+    }
+
+    /// Gets the ticks left before a timed mode ends
+    pub fn ticks_left(&self) -> Option<u64> {
+        self.rules.mode.time_limit().map(|limit| limit.saturating_sub(self.tick))
+    }
+
+    /// Ends the game as a success
+    fn complete(&mut self) {
+        self.completed = true;
+        self.game_phase = GamePhase::GameOver;
+    }
+
+    /// Checks and clears any lines the player has created, returning the count
+    fn clear_lines(&mut self) -> u32 {
+        // Dig rows move down with the rest of the board, the full ones go
+        let full = (1 << self.board.width()) - 1;
+        let mut y = 0;
+        self.dig_rows.retain(|_| {
+            y += 1;
+            self.board.row(y - 1) != full
+        });
+        let cleared = self.board.clear_lines();
+        let mut dig_rows = vec![false; cleared as usize];
+        dig_rows.append(&mut self.dig_rows);
+        self.dig_rows = dig_rows;
+        cleared
+    }
+
+    /// Moves the player to the left or right, returns false on fail
+    pub fn move_player_horizontal(&mut self, dir: Direction) -> bool {
         // Horizontal only
         if let Direction::Up | Direction::Down = dir {
-            return;
+            return false;
         }
 
         // Get new position
@@ -180,58 +766,52 @@ impl GameState {
             &self.rotation,
             &new_pos,
         ) {
-            return;
+            return false;
         }
 
         // Move piece
         self.player_pos.x += dir.to_value();
+        self.last_kick = None;
+        self.on_player_moved();
+        true
     }
 
     /// Drops the player onto the ghost block
     pub fn hard_drop(&mut self) {
         // get new drop height
-        let shape = self.current_shape.get_shape(&self.rotation);
+        let shape = self.shape_cells(self.current_shape, self.rotation);
         let drop_y = self.get_drop_position(&shape);
 
         // Change player position and place
+        let cells = (drop_y - self.player_pos.y) as u32;
         self.player_pos.y = drop_y;
+        if cells > 0 {
+            self.award_drop(true, cells);
+            self.last_kick = None;
+        }
         self.place_and_reset();
     }
 
     /// Determines the drop height of the current shape
     pub fn get_drop_position(&self, shape: &[[bool;4];4]) -> i16 {
-        let mut ghost_y = self.player_pos.y; // Starting y
-
-        'drop: loop {
-            for dx in 0..4 {
-                for dy in 0..4 {
-                    if shape[dy][dx] { // Shape tile found
-                        let x = self.player_pos.x + dx as i16;
-                        let y = ghost_y + dy as i16;
-
-                        // Stop if tile would collide with bottom or tile
-                        if y + 1 >= 22 ||
-                        (y+1 >= 0 && self.board[(y+1) as usize][x as usize].is_block()) {
-                            break 'drop;
-                        }
-                    }
-                }
-            }
-
-            ghost_y += 1;
-        }
-
-        ghost_y
+        self.board.drop_y(board::piece_mask(shape), self.player_pos.x, self.player_pos.y)
     }
 
     /// Moves the player to starting position
     pub fn player_to_top(&mut self) {
         // Set new positions
-        let new_pos = self.current_shape.get_spawn_offsets();
+        let new_pos = self.rules.spawn(&self.current_shape);
         let new_rot = Rotation::R0;
 
         self.player_pos = new_pos;
         self.rotation = new_rot;
+        self.last_kick = None;
+        self.fall_progress = 0;
+
+        // Fresh lock state for the new piece
+        self.lock_timer = 0;
+        self.move_resets = 0;
+        self.lowest_row = self.player_pos.y;
     }
 
     /// Attempts to move the player down one tile, returns false on fail
@@ -251,6 +831,8 @@ impl GameState {
 
         // Move player
         self.player_pos.y +=1;
+        self.last_kick = None;
+        self.on_player_fell();
         true
     }
 
@@ -259,30 +841,20 @@ impl GameState {
     /// Up and down are the only valid directions and will
     /// be interpreted as cw and ccw respectively.
     pub fn rotate_player(&mut self, dir: Direction) {
-        // Get new rotation and offsets
+        // Get new rotation
         let new_rot = match dir {
             Direction::Up => self.rotation.rotate_cw(),
             Direction::Down => self.rotation.rotate_ccw(),
             _ => unreachable!() 
         };
-        let offsets = self.current_shape.get_kick_data(&self.rotation, &new_rot);
-
-        // Iterate each offset 
-        for (dx, dy) in offsets {
-            // Calculate new position
-            let new_pos = Coord { x: self.player_pos.x + dx, y: self.player_pos.y + dy };
-
-            // Attempt placement
-            if self.can_place(
-                &self.current_shape, 
-                &new_rot, 
-                &new_pos
-            ) {
-                // Placement possible! 
-                self.player_pos = new_pos;
-                self.rotation = new_rot;
-                break;
-            }
+
+        // Try each kick until one fits
+        let rotated = self.rules.rotation_system.rotate(&self.board, self.current_shape, self.rotation, new_rot, &self.player_pos);
+        if let Some((new_pos, kick)) = rotated {
+            self.player_pos = new_pos;
+            self.rotation = new_rot;
+            self.last_kick = Some(kick);
+            self.on_player_moved();
         }
     }
 
@@ -295,7 +867,7 @@ impl GameState {
 
         // Swap shape and held
         let temp = self.current_shape;
-        self.current_shape = self.held.unwrap_or(self.get_next_shape());
+        self.current_shape = self.held.unwrap_or_else(|| self.get_next_shape());
         self.held = Some(temp);
         self.player_to_top();
 
@@ -316,11 +888,8 @@ impl GameState {
         // Remove last item in queue
         let _ = self.shape_queue.pop();
 
-        // If queue is less then 7, add a new 7bag
-        if self.shape_queue.len() < 7 {
-            let mut new_bag = create_new_7_bag().to_vec();
-            self.shape_queue.append(&mut new_bag);
-        }
+        // Keep the queue full
+        self.shape_queue.push(self.generator.next_shape());
 
         // Debug print queue colors
         //stdout().queue(MoveTo(0,0)).unwrap();
@@ -334,33 +903,40 @@ impl GameState {
     }
 }
 
-/// Creates a new 7 bag array
-pub fn create_new_7_bag() -> [Shape;7]{
-    let mut new_queue: [Option<Shape>;7] = [None;7];
-
-    // Assign each shape
-    for x in 0..7 {
-        // Loop until available shape found
-        'new_shape: loop {
-            // Get new shape
-            let new_shape = Shape::random();
-
-            // Check if shape exists in queue
-            for shape in new_queue {
-                if let Some(shape) = shape {
-                    if shape == new_shape { // Shape already in queue
-                        continue 'new_shape;
-                    }
-                }
-            }
-
-            // Shape not found in queue
-            new_queue[x] = Some(new_shape);
-            break 'new_shape;
-        }
+/// Classifies a T piece at rest using the 3 corner rule, given the kick that rotated it there
+///
+/// Works from the cells, so it holds for every rotation system.
+pub(crate) fn classify_tspin(board: &Board, cells: &[[bool; 4]; 4], pos: &Coord, kick: usize) -> TSpin {
+    // The center is the cell with 3 neighbours, the T points away from the missing one
+    let cell = |x: i16, y: i16| (0..4).contains(&x) && (0..4).contains(&y) && cells[y as usize][x as usize];
+    let sides = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let Some((cx, cy, (bx, by))) = (0..16)
+        .map(|i| (i % 4, i / 4))
+        .filter(|(x, y)| cell(*x, *y))
+        .find_map(|(x, y)| {
+            let missing: Vec<_> = sides.into_iter().filter(|(dx, dy)| !cell(x + dx, y + dy)).collect();
+            (missing.len() == 1).then(|| (x, y, missing[0]))
+        })
+    else {
+        return TSpin::None;
+    };
+
+    // Corners of the 3x3 box around the T center, walls count as filled
+    let corners = [(-1, -1), (1, -1), (1, 1), (-1, 1)]
+        .map(|(dx, dy)| ((dx, dy), board.is_filled(pos.x + cx + dx, pos.y + cy + dy)));
+    if corners.iter().filter(|(_, filled)| *filled).count() < 3 {
+        return TSpin::None;
     }
 
-    // Return queue
-    new_queue.map(|shape| shape.unwrap())
-}
+    // Front corners are the two the T points at, away from the back
+    let front = corners.iter()
+        .filter(|((dx, dy), _)| dx * bx + dy * by < 0)
+        .all(|(_, filled)| *filled);
 
+    // The last kick (TST and fin kicks) always upgrades a mini
+    if front || kick == 4 {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
@@ -0,0 +1,164 @@
+use std::{cmp::Ordering, io, path::{Path, PathBuf}};
+
+use crate::{game_state::GameState, rules::GameMode, storage};
+
+/// Entries kept per mode
+pub const TABLE_SIZE: usize = 10;
+
+/// Longest name that can be entered
+pub const MAX_NAME_LEN: usize = 12;
+
+/// First line of a high score file, bumped if the format changes
+const HEADER: &str = "# jordtris highscores v1";
+
+/// A finished game on the high score table
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HighScore {
+    pub name: String,
+    /// Name of the mode the game was played in
+    pub mode: String,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    /// Length of the game in ticks
+    pub ticks: u64,
+    /// When the game finished, in seconds since the unix epoch
+    pub date: u64,
+    pub seed: u64,
+}
+
+impl HighScore {
+    /// Creates an entry for a finished game
+    pub fn from_game(name: &str, game: &GameState) -> Self {
+        HighScore {
+            name: clean_name(name),
+            mode: game.rules.mode.name(),
+            score: game.score,
+            lines: game.lines,
+            level: game.level,
+            ticks: game.tick,
+            date: storage::unix_time(),
+            seed: game.seed,
+        }
+    }
+
+    /// Compares entries of the same mode, better entries first
+    ///
+    /// Timed modes rank the fastest game first, the rest the highest score.
+    /// Earlier games win ties.
+    pub fn rank(&self, other: &HighScore) -> Ordering {
+        let by_time = GameMode::from_name(&self.mode).is_some_and(|mode| mode.ranks_by_time());
+        let result = if by_time {
+            self.ticks.cmp(&other.ticks)
+        } else {
+            other.score.cmp(&self.score)
+        };
+        result.then(self.date.cmp(&other.date))
+    }
+
+    /// Writes the entry as a line of the high score file
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.name, self.mode, self.score, self.lines,
+            self.level, self.ticks, self.date, self.seed,
+        )
+    }
+
+    /// Reads an entry from a line of the high score file
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let entry = HighScore {
+            name: fields.next()?.to_string(),
+            mode: fields.next()?.to_string(),
+            score: fields.next()?.parse().ok()?,
+            lines: fields.next()?.parse().ok()?,
+            level: fields.next()?.parse().ok()?,
+            ticks: fields.next()?.parse().ok()?,
+            date: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+        };
+        Some(entry)
+    }
+}
+
+/// The local high score table for every mode
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Default location of the high score file
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_file("highscores.tsv")
+    }
+
+    /// Loads a high score file, a missing file is an empty table
+    ///
+    /// Lines that can't be read are skipped rather than losing the whole table.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let entries = storage::load_lines(path, HEADER)?
+            .iter()
+            .filter_map(|line| HighScore::from_line(line))
+            .collect();
+        Ok(HighScores { entries })
+    }
+
+    /// Saves the table, creating the directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        storage::save_lines(path, HEADER, self.entries.iter().map(HighScore::to_line))
+    }
+
+    /// Gets the best entries of a mode, best first
+    pub fn top(&self, mode: &str) -> Vec<&HighScore> {
+        let mut top: Vec<&HighScore> = self.entries.iter()
+            .filter(|entry| entry.mode == mode)
+            .collect();
+
+        top.sort_by(|a, b| a.rank(b));
+        top.truncate(TABLE_SIZE);
+        top
+    }
+
+    /// Determines if an entry would make it onto its mode's table
+    pub fn qualifies(&self, entry: &HighScore) -> bool {
+        let top = self.top(&entry.mode);
+        top.len() < TABLE_SIZE || top.last().is_some_and(|last| entry.rank(last).is_lt())
+    }
+
+    /// Adds an entry, dropping any that fall off its mode's table
+    pub fn insert(&mut self, entry: HighScore) {
+        let mode = entry.mode.clone();
+        self.entries.push(entry);
+
+        // Keep only what is shown
+        let keep: Vec<HighScore> = self.top(&mode).into_iter().cloned().collect();
+        self.entries.retain(|entry| entry.mode != mode);
+        self.entries.extend(keep);
+    }
+
+    /// Gets every mode with at least one entry
+    pub fn modes(&self) -> Vec<String> {
+        let mut modes: Vec<String> = vec![];
+        for entry in &self.entries {
+            if !modes.contains(&entry.mode) {
+                modes.push(entry.mode.clone());
+            }
+        }
+        modes
+    }
+}
+
+/// Strips characters that can't be stored and limits the length of a name
+pub fn clean_name(name: &str) -> String {
+    let name: String = name.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LEN)
+        .collect();
+
+    match name.trim() {
+        "" => "Anonymous".to_string(),
+        name => name.to_string(),
+    }
+}
@@ -0,0 +1,41 @@
+//! Headless Jordtris engine
+//!
+//! Everything needed to drive a game of Jordtris (board, piece movement,
+//! SRS, hold, queue and line clears) without a terminal. The crossterm
+//! front end lives in the `jordtris` binary behind the `tui` feature.
+
+pub mod board;
+pub mod bot;
+pub mod game_state;
+pub mod highscores;
+pub mod json;
+pub mod movegen;
+pub mod netplay;
+pub mod personal_bests;
+pub mod randomizer;
+pub mod replay;
+pub mod rotation_system;
+pub mod rules;
+pub mod scoring;
+pub mod shapes;
+pub mod spectate;
+pub mod storage;
+pub mod tbp;
+pub mod versus;
+
+pub use board::Board;
+pub use bot::Bot;
+pub use game_state::{Button, Coord, Direction, GamePhase, GameState, Input, SPLIT_LINES, TICKS_PER_SECOND};
+pub use highscores::{HighScore, HighScores};
+pub use movegen::Placement;
+pub use netplay::Session;
+pub use personal_bests::{PersonalBest, PersonalBests};
+pub use randomizer::Randomizer;
+pub use replay::{Replay, ReplayPlayer};
+pub use rotation_system::RotationSystem;
+pub use rules::{AttackTable, BoardSize, GameMode, Gravity, Handling, LockMode, Ruleset};
+pub use scoring::{ClearScore, GameEvent, TSpin};
+pub use shapes::{Rotation, Shape, ShapeColor};
+pub use spectate::SpectatorServer;
+pub use tbp::TbpBot;
+pub use versus::Outcome;
@@ -1,38 +1,297 @@
 use core::time;
-use std::{io::{self, stdout, Stdout, Write}, process::exit, thread::sleep, time::{Duration, Instant}};
-use crossterm::{cursor::{self, MoveDown, MoveTo}, event::{poll, read, Event, KeyCode, KeyModifiers}, execute, style::{self, Print}, terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType}, QueueableCommand};
-use game_state::{Coord, GamePhase, GameState};
-use shapes::{Rotation, Shape};
-
-// All shape logic
-mod shapes;
-mod game_state;
+use std::{io::{self, stdout, Stdout, Write}, net::TcpListener, path::PathBuf, str::FromStr, sync::atomic::{AtomicBool, Ordering}, thread::sleep, time::{Duration, Instant}};
+use crossterm::{cursor::{self, MoveTo}, event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags}, execute, style::{self, Print, StyledContent, Stylize}, terminal::{self, disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, Clear, ClearType}, QueueableCommand};
+use jordtris::{highscores::{MAX_NAME_LEN, TABLE_SIZE}, netplay, storage, BoardSize, Bot, Button, GameEvent, GameMode, HighScore, HighScores, PersonalBest, PersonalBests, Randomizer, Replay, RotationSystem, GamePhase, GameState, Input, LockMode, Rotation, Ruleset, Session, ShapeColor, SpectatorServer, TICKS_PER_SECOND};
 
 const INFO_WIDTH: usize = 16;
 
-/// A direction
-#[allow(unused)]
-enum Direction {
-    Left,
-    Right,
-    Up, 
-    Down,
+/// Keys bound to each button
+const KEY_BINDINGS: [(KeyCode, Button); 8] = [
+    (KeyCode::Left, Button::Left),
+    (KeyCode::Right, Button::Right),
+    (KeyCode::Down, Button::SoftDrop),
+    (KeyCode::Char(' '), Button::HardDrop),
+    (KeyCode::Up, Button::RotateCw),
+    (KeyCode::Char('x'), Button::RotateCw),
+    (KeyCode::Char('z'), Button::RotateCcw),
+    (KeyCode::Char('c'), Button::Hold),
+];
+
+/// Keys that pause the game
+const PAUSE_KEYS: [KeyCode; 2] = [KeyCode::Esc, KeyCode::Char('p')];
+
+/// Entries in the pause menu
+const PAUSE_MENU: [&str; 4] = ["Resume", "Restart", "Controls", "Quit"];
+
+/// Set once the terminal has been asked to report key releases
+static ENHANCED_KEYBOARD: AtomicBool = AtomicBool::new(false);
+
+/// Lines taken by the info section, the fewest in a drawn frame
+const FRAME_HEIGHT: usize = 25;
+
+// Front end modes beyond the main game
+mod tui {
+    pub mod netplay;
+    pub mod bot;
+    pub mod replay;
+    pub mod versus;
+}
+
+/// What the program was asked to do
+enum Command {
+    Play,
+    /// Watch a replay file
+    Replay(PathBuf),
+    /// Two players on one keyboard, or one against the built in bot
+    Versus { bot: bool },
+    /// Watch the built in bot play
+    Demo,
+    /// Wait for a networked opponent on a port
+    Host(u16),
+    /// Play a networked opponent hosting at an address
+    Join(String),
+    /// Watch an external bot, run with these arguments
+    Tbp(Vec<String>),
+}
+
+/// Command line options
+struct Options {
+    command: Command,
+    seed: Option<u64>,
+    rules: Ruleset,
+    /// Local port to stream the game to spectators on
+    spectate_port: Option<u16>,
+    /// Fastest a bot is allowed to place pieces
+    bot_pps: f64,
+}
+
+impl Options {
+    /// Parses the options from the process arguments, exiting on bad input
+    fn from_args() -> Self {
+        let mut options = Options { command: Command::Play, seed: None, rules: Ruleset::default(), spectate_port: None, bot_pps: 2.0 };
+        let mut args = std::env::args().skip(1);
+        let mut goal_lines = None;
+        let mut time_limit = None;
+        let mut garbage_messiness = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                // Fixed seed for the piece sequence
+                "--seed" => options.seed = Some(number_arg(&arg, args.next())),
+                "--level" => options.rules.start_level = number_arg(&arg, args.next()),
+                "--mode" => {
+                    options.rules.mode = match args.next().as_deref() {
+                        Some("endless") => GameMode::Endless,
+                        Some("sprint") => GameMode::SPRINT,
+                        Some("ultra") => GameMode::ULTRA,
+                        Some("dig") => GameMode::DIG,
+                        _ => usage("--mode expects endless, sprint, ultra or dig"),
+                    };
+                },
+                "--lines" => goal_lines = Some(number_arg(&arg, args.next())),
+                "--time" => time_limit = Some(number_arg(&arg, args.next())),
+                "--messiness" => garbage_messiness = Some(number_arg(&arg, args.next())),
+                "--das" => options.rules.handling.das = number_arg(&arg, args.next()),
+                "--arr" => options.rules.handling.arr = number_arg(&arg, args.next()),
+                "--sdf" => {
+                    let value = args.next();
+                    options.rules.handling.soft_drop_factor = match value.as_deref() {
+                        Some("inf") => None,
+                        _ => match number_arg(&arg, value) {
+                            0 => usage("--sdf expects a factor of at least 1, or inf"),
+                            factor => Some(factor),
+                        },
+                    };
+                },
+                "--lock" => {
+                    options.rules.lock_mode = args.next()
+                        .and_then(|name| LockMode::from_name(&name))
+                        .unwrap_or_else(|| usage("--lock expects extended, infinite, step or classic"));
+                },
+                "--randomizer" => {
+                    options.rules.randomizer = args.next()
+                        .and_then(|name| Randomizer::from_name(&name))
+                        .unwrap_or_else(|| usage("--randomizer expects 7bag, 14bag, random, nes, tgm or bag+1"));
+                },
+                "--rotation" => {
+                    options.rules.rotation_system = args.next()
+                        .and_then(|name| RotationSystem::from_name(&name))
+                        .unwrap_or_else(|| usage("--rotation expects srs, srs+, ars, nrs or none"));
+                },
+                "--width" => options.rules.board.width = number_arg(&arg, args.next()),
+                "--height" => options.rules.board.height = number_arg(&arg, args.next()),
+                "--hidden-rows" => options.rules.board.hidden_rows = number_arg(&arg, args.next()),
+                "--spectate-port" => options.spectate_port = Some(number_arg(&arg, args.next())),
+                "versus" => options.command = Command::Versus { bot: false },
+                "--bot" => match &mut options.command {
+                    Command::Versus { bot } => *bot = true,
+                    _ => usage("--bot only applies to versus"),
+                },
+                "demo" => options.command = Command::Demo,
+                "host" => options.command = Command::Host(netplay::DEFAULT_PORT),
+                "--port" => match &mut options.command {
+                    Command::Host(port) => *port = number_arg(&arg, args.next()),
+                    _ => usage("--port only applies to host"),
+                },
+                "--pps" => {
+                    options.bot_pps = number_arg(&arg, args.next());
+                    if options.bot_pps.is_nan() || options.bot_pps <= 0.0 {
+                        usage("--pps expects a speed above 0");
+                    }
+                },
+                "tbp" => {
+                    // The bot's command line comes as one argument
+                    let command: Vec<String> = args.next().unwrap_or_default()
+                        .split_whitespace()
+                        .map(String::from)
+                        .collect();
+                    if command.is_empty() {
+                        usage("tbp expects a bot command");
+                    }
+                    options.command = Command::Tbp(command);
+                },
+                "join" => match args.next() {
+                    // The default port can be left off
+                    Some(addr) if addr.contains(':') => options.command = Command::Join(addr),
+                    Some(addr) => options.command = Command::Join(format!("{addr}:{}", netplay::DEFAULT_PORT)),
+                    None => usage("join expects an address"),
+                },
+                "replay" => match args.next() {
+                    Some(path) => options.command = Command::Replay(PathBuf::from(path)),
+                    None => usage("replay expects a file"),
+                },
+                "-h" | "--help" => usage(""),
+                _ => usage(&format!("unknown argument '{arg}'")),
+            }
+        }
+
+        // Goals are set once the mode is known
+        if let Some(goal) = goal_lines {
+            match &mut options.rules.mode {
+                GameMode::Sprint { lines } | GameMode::Dig { lines, .. } if goal > 0 => *lines = goal,
+                GameMode::Sprint { .. } | GameMode::Dig { .. } => usage("--lines expects at least 1 line"),
+                _ => usage("--lines only applies to --mode sprint or dig"),
+            }
+        }
+        if let Some(percent) = garbage_messiness {
+            match &mut options.rules.mode {
+                GameMode::Dig { messiness, .. } if percent <= 100 => *messiness = percent,
+                GameMode::Dig { .. } => usage("--messiness expects a percent up to 100"),
+                _ => usage("--messiness only applies to --mode dig"),
+            }
+        }
+        if let Some(limit) = time_limit {
+            match &mut options.rules.mode {
+                GameMode::Ultra { seconds } if limit > 0 => *seconds = limit,
+                GameMode::Ultra { .. } => usage("--time expects at least 1 second"),
+                _ => usage("--time only applies to --mode ultra"),
+            }
+        }
+
+        let board = options.rules.board;
+        if !board.is_valid() {
+            usage(&format!(
+                "the board must be {} to {} columns wide and have at least {} rows below the hidden ones, up to {} in all",
+                BoardSize::MIN_WIDTH, BoardSize::MAX_WIDTH, BoardSize::MIN_VISIBLE_ROWS, BoardSize::MAX_HEIGHT,
+            ));
+        }
+        if options.spectate_port.is_some() && !matches!(options.command, Command::Play) {
+            usage("--spectate-port only applies to single player games");
+        }
+        if matches!(options.command, Command::Tbp(_)) && (board.width != 10 || board.height > 40) {
+            usage("tbp bots only play on boards 10 wide and up to 40 rows");
+        }
+        if matches!(options.command, Command::Tbp(_)) && options.rules.rotation_system != RotationSystem::Srs {
+            usage("tbp bots only play with srs rotation");
+        }
+
+        options
+    }
+
+    /// Creates a new game using the seed option if one was given
+    fn new_game(&self) -> GameState {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        GameState::with_rules(seed, self.rules.clone())
+    }
+}
+
+/// Parses the value following a numeric argument, exiting if it is missing or invalid
+fn number_arg<T: FromStr>(name: &str, value: Option<String>) -> T {
+    match value.and_then(|v| v.parse().ok()) {
+        Some(value) => value,
+        None => usage(&format!("{name} expects a number")),
+    }
+}
+
+/// Waits for an opponent to join a game hosted with the options, exiting on failure
+fn host(port: u16, options: &Options) -> Session {
+    let session = TcpListener::bind(("0.0.0.0", port)).and_then(|listener| {
+        println!("Waiting for an opponent on port {port}");
+        let game = options.new_game();
+        Session::host(&listener, game.seed, game.rules)
+    });
+    session.unwrap_or_else(|err| {
+        eprintln!("error: could not host a game: {err}");
+        std::process::exit(1);
+    })
+}
+
+/// Joins a hosted game, exiting on failure
+fn join(addr: &str) -> Session {
+    println!("Connecting to {addr}");
+    Session::join(addr).unwrap_or_else(|err| {
+        eprintln!("error: could not join {addr}: {err}");
+        std::process::exit(1);
+    })
+}
+
+/// Prints usage information and exits
+fn usage(error: &str) -> ! {
+    if !error.is_empty() {
+        eprintln!("error: {error}");
+    }
+    eprintln!("usage: jordtris [--seed <number>] [--level <number>] [--lock <extended|infinite|step|classic>]");
+    eprintln!("                [--das <frames>] [--arr <frames>] [--sdf <factor|inf>]");
+    eprintln!("                [--mode <endless|sprint|ultra|dig>] [--lines <number>] [--time <seconds>]");
+    eprintln!("                [--messiness <percent>] [--spectate-port <number>]");
+    eprintln!("                [--randomizer <7bag|14bag|random|nes|tgm|bag+1>] [--rotation <srs|srs+|ars|nrs|none>]");
+    eprintln!("                [--width <columns>] [--height <rows>] [--hidden-rows <rows>]");
+    eprintln!("       jordtris versus [--bot] [--pps <pieces per second>] [options]");
+    eprintln!("       jordtris demo [--pps <pieces per second>] [options]");
+    eprintln!("       jordtris host [--port <number>] [options]");
+    eprintln!("       jordtris join <address[:port]>");
+    eprintln!("       jordtris tbp <bot command> [--pps <pieces per second>] [options]");
+    eprintln!("       jordtris replay <file>");
+    std::process::exit(if error.is_empty() { 0 } else { 1 });
+}
+
+/// Terminal rendering for board colors
+trait ColorTile {
+    /// Returns a tile styled based on the color
+    fn color_tile(&self) -> StyledContent<&'static str>;
 }
 
-impl Direction {
-    // Returns a value of 1 or -1 based on the direction
-    pub fn to_value(&self) -> i16 {
+impl ColorTile for ShapeColor {
+    fn color_tile(&self) -> StyledContent<&'static str> {
         match self {
-            Direction::Left => -1,
-            Direction::Right => 1,
-            Direction::Up => -1,
-            Direction::Down => 1,
+            ShapeColor::Cyan => "██".cyan(),
+            ShapeColor::Blue => "██".blue(),
+            ShapeColor::Orange => "██".dark_red(),
+            ShapeColor::Yellow => "██".yellow(),
+            ShapeColor::Green => "██".green(),
+            ShapeColor::Purple => "██".magenta(),
+            ShapeColor::Red => "██".red(),
+            ShapeColor::Garbage => "▓▓".dark_grey(),
+            _ => "██".reset()
         }
     }
 }
 
 /// Cleans the program and exits
 fn clean() {
+    if ENHANCED_KEYBOARD.load(Ordering::Relaxed) {
+        let _ = execute!(stdout(), PopKeyboardEnhancementFlags);
+    }
     execute!( stdout(),
         cursor::Show,
         terminal::LeaveAlternateScreen,
@@ -43,8 +302,8 @@ fn clean() {
 }
 
 /// Setup program 
-fn setup() -> GameState {
-    let _ = enable_raw_mode().unwrap(); // Disable buffering
+fn setup() {
+    enable_raw_mode().unwrap(); // Disable buffering
 
     // Prepare terminal
     execute!( stdout(),
@@ -52,89 +311,209 @@ fn setup() -> GameState {
         terminal::EnterAlternateScreen,
     ).unwrap();
 
-    GameState::new() // Return new gamestate
+    // Ask for key release events so the engine can time auto shift
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(stdout(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)).unwrap();
+        ENHANCED_KEYBOARD.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Maps wall clock time onto engine ticks
+struct TickClock {
+    last: Instant,
+    lag: Duration,
 }
-/// Updates the game state based on player keypresses
-fn update(game: &mut GameState) -> Result<(), io::Error> {
-    // Get fall duration
-    let fall_interval = Duration::from_millis(500); // TODO: Adjust for difficulty
-    let lock_interval = Duration::from_millis(500); // 500ms lock delay 
 
-    // Check if fall is requred
-    if game.last_fall.elapsed() >= fall_interval {
-        // Fall piece and update last fall
-        game.last_fall = Instant::now();
-        let fell = game.fall_player();
+impl TickClock {
+    /// Creates a clock starting now
+    fn new() -> Self {
+        TickClock { last: Instant::now(), lag: Duration::ZERO }
+    }
+
+    /// Restarts the clock, dropping any time that has built up
+    fn reset(&mut self) {
+        *self = TickClock::new();
+    }
+
+    /// Returns the number of whole ticks that have passed since the last call
+    fn ticks_due(&mut self) -> u32 {
+        let tick_time = Duration::from_secs(1) / TICKS_PER_SECOND;
 
-        // If piece could not fall, check lock delay
-        if !fell && game.last_input.elapsed() >= lock_interval {
-            game.place_and_reset();
+        // Accumulate elapsed time
+        let now = Instant::now();
+        self.lag += now - self.last;
+        self.last = now;
+
+        // Consume whole ticks
+        let mut ticks = 0;
+        while self.lag >= tick_time {
+            self.lag -= tick_time;
+            ticks += 1;
         }
+        ticks
     }
+}
 
-    // Screen Event poll
-    while poll(time::Duration::from_secs(0))? {
-        // read event
-        match read()? {
-            // Keypress
-            Event::Key(evt) => {
-                // Move right
-                if evt.code == KeyCode::Right && !evt.kind.is_release() {
-                    // Try to move player
-                    game.move_player_horizontal(Direction::Right);
-                    game.last_input = Instant::now();
-                }
+/// Front end state shown alongside the board
+struct Hud {
+    /// Name of the last clear and the tick it stops being shown at
+    action: Option<(String, u64)>,
+    /// Record splits are compared against
+    best: Option<PersonalBest>,
+    /// Splits already shown
+    splits_shown: usize,
+}
 
-                // Move left
-                if evt.code == KeyCode::Left && !evt.kind.is_release() {
-                    game.move_player_horizontal(Direction::Left);
-                    game.last_input = Instant::now();
-                }
+impl Hud {
+    /// Creates an empty hud
+    fn new() -> Self {
+        Hud { action: None, best: None, splits_shown: 0 }
+    }
 
-                // Rotate cw (up)
-                if (evt.code == KeyCode::Up || evt.code == KeyCode::Char('x')) && !evt.kind.is_release() {
-                    game.rotate_player(Direction::Up);
-                    game.last_input = Instant::now();
-                }
+    /// Creates a hud for a new game, with the personal best of timed modes to race
+    fn for_game(game: &GameState) -> Self {
+        let mut hud = Hud::new();
+        if game.rules.mode.ranks_by_time() {
+            hud.best = PersonalBests::default_path()
+                .and_then(|path| PersonalBests::load(&path).ok())
+                .and_then(|bests| bests.get(&game.rules.mode.name()).cloned());
+        }
+        hud
+    }
 
-                // Move player down
-                if evt.code == KeyCode::Down && !evt.kind.is_release() {
-                    let _ = game.fall_player();
-                }
+    /// Updates the hud from the events the engine produced, returning them
+    fn handle_events(&mut self, game: &mut GameState) -> Vec<GameEvent> {
+        let until = game.tick + 2 * TICKS_PER_SECOND as u64;
+        let events = game.take_events();
+        for event in &events {
+            if let GameEvent::Clear(clear) = event {
+                self.action = Some((clear.label(), until));
+            }
+        }
 
-                // Rotate ccw (down)
-                if evt.code == KeyCode::Char('z') && !evt.kind.is_release() {
-                    game.rotate_player(Direction::Down);
-                    game.last_input = Instant::now();
-                }
+        // New splits take over from the clear name
+        while self.splits_shown < game.splits.len() {
+            let tick = game.splits[self.splits_shown];
+            let split = match self.best.as_ref().and_then(|best| best.split_delta(self.splits_shown, tick)) {
+                Some(delta) => storage::format_delta(delta),
+                None => storage::format_ticks(tick),
+            };
+            self.action = Some((format!("SPLIT {split}"), until));
+            self.splits_shown += 1;
+        }
 
-                // Hard drop
-                if evt.code == KeyCode::Char(' ') && !evt.kind.is_release() {
-                    game.hard_drop();
-                    game.last_input = Instant::now();
-                }
+        events
+    }
+}
 
-                // Hold piece
-                if evt.code == KeyCode::Char('c') && !evt.kind.is_release() {
-                    game.hold();
-                }
+/// Tracks which buttons are held so key events become engine inputs
+struct Keyboard {
+    /// Whether the terminal reports key releases
+    releases: bool,
+    /// Keys bound to each button
+    bindings: &'static [(KeyCode, Button)],
+    held: Vec<Button>,
+}
 
-                // Control + c
-                if evt.code == KeyCode::Char('c') 
-                    && evt.modifiers.contains(KeyModifiers::CONTROL)
-                {
-                    clean(); // Clean and exit game
-                }
-            },
+impl Keyboard {
+    /// Creates a keyboard with the default key bindings
+    fn new() -> Self {
+        Keyboard::with_bindings(&KEY_BINDINGS)
+    }
 
-            // Ignore other events
-            _ => {},
+    /// Creates a keyboard, using release events if the terminal has them
+    fn with_bindings(bindings: &'static [(KeyCode, Button)]) -> Self {
+        // Windows consoles always report releases
+        let releases = cfg!(windows) || ENHANCED_KEYBOARD.load(Ordering::Relaxed);
+        Keyboard { releases, bindings, held: vec![] }
+    }
+
+    /// Gets the button bound to a key
+    fn button(&self, code: KeyCode) -> Option<Button> {
+        self.bindings.iter()
+            .find(|(key, _)| *key == code)
+            .map(|(_, button)| *button)
+    }
+
+    /// Converts a key event into engine inputs, ignoring keys that aren't bound
+    fn handle(&mut self, evt: KeyEvent, inputs: &mut Vec<Input>) {
+        let Some(button) = self.button(evt.code) else {
+            return;
+        };
+
+        // Without releases every press is a tap, the terminal's key repeat moves
+        if !self.releases {
+            if !evt.kind.is_release() {
+                inputs.push(Input::Press(button));
+                inputs.push(Input::Release(button));
+            }
+            return;
+        }
+
+        if evt.kind.is_release() {
+            self.held.retain(|held| *held != button);
+            inputs.push(Input::Release(button));
+        } else if !self.held.contains(&button) {
+            // Repeats of a held key are left to the engine
+            self.held.push(button);
+            inputs.push(Input::Press(button));
+        }
+    }
+}
+
+/// Converts player keypresses into engine inputs
+fn read_inputs(game: &mut GameState, keyboard: &mut Keyboard, inputs: &mut Vec<Input>) -> Result<(), io::Error> {
+    // Screen Event poll
+    while poll(time::Duration::from_secs(0))? {
+        // read event, ignoring anything that is not a keypress
+        if let Event::Key(evt) = read()? {
+            // Control + c
+            if evt.code == KeyCode::Char('c') 
+                && evt.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                clean(); // Clean and exit game
+            }
+
+            // Pause
+            if PAUSE_KEYS.contains(&evt.code) && !evt.kind.is_release() {
+                game.pause();
+            }
+
+            keyboard.handle(evt, inputs);
         }
     }
 
     Ok(())
 }
 
+/// Updates the game state based on player keypresses and elapsed time
+fn update(game: &mut GameState, hud: &mut Hud, clock: &mut TickClock, keyboard: &mut Keyboard, inputs: &mut Vec<Input>) -> Result<(), io::Error> {
+    read_inputs(game, keyboard, inputs)?;
+
+    // Run every tick that is due, inputs are applied on the first one
+    for _ in 0..clock.ticks_due() {
+        game.step(inputs);
+        inputs.clear();
+    }
+    hud.handle_events(game);
+
+    Ok(())
+}
+
+/// Gets the left edge of a board row, filled from the bottom by incoming garbage
+///
+/// Garbage that will rise on the next lock is red, garbage still delayed is yellow.
+fn garbage_meter_tile(game: &GameState, y: usize) -> String {
+    let height = (game.board.height() - 1 - y) as u32;
+    if height < game.ready_garbage() {
+        "█".red().to_string()
+    } else if height < game.incoming_garbage() {
+        "█".yellow().to_string()
+    } else {
+        "│".to_string()
+    }
+}
+
 /// Determines if the tile in an area overlaps with a player tile
 fn is_player_tile(x: i16, y: i16, px: i16, py: i16, shape: &[[bool;4];4]) -> bool {
     if x >= px && x < px + 4 && y >= py && y < py + 4 {
@@ -148,9 +527,9 @@ fn is_player_tile(x: i16, y: i16, px: i16, py: i16, shape: &[[bool;4];4]) -> boo
 
 /// Determines if a tile overlaps with a ghost preview
 fn is_ghost_tile(x: usize, y: usize, gx: i16, py: i16, shape: &[[bool;4];4]) -> bool {
-    for dy in 0..4 {
-        for dx in 0..4 {
-            if shape[dy][dx] {
+    for (dy, row) in shape.iter().enumerate() {
+        for (dx, filled) in row.iter().enumerate() {
+            if *filled {
                 let gx = gx + dx as i16;
                 let gy = py + dy as i16;
 
@@ -164,7 +543,7 @@ fn is_ghost_tile(x: usize, y: usize, gx: i16, py: i16, shape: &[[bool;4];4]) ->
 }
 
 /// Appends the line at an index with a padding line for an info section
-fn info_padding_line(frames: &mut Vec<String>, idx: usize) {
+fn info_padding_line(frames: &mut [String], idx: usize) {
     if let Some(line) = frames.get_mut(idx) {
         *line = format!( 
             "{}  │{}│",
@@ -174,32 +553,108 @@ fn info_padding_line(frames: &mut Vec<String>, idx: usize) {
     }
 }
 
+/// Appends the line at an index with centered text for an info section
+fn info_text_line(frames: &mut [String], idx: usize, text: &str) {
+    if let Some(line) = frames.get_mut(idx) {
+        let total_pad = INFO_WIDTH.saturating_sub(text.chars().count());
+        let left_pad = total_pad / 2;
+        let right_pad = total_pad - left_pad;
+
+        *line = format!( 
+            "{}  │{}{}{}│",
+            line,
+            " ".repeat(left_pad),
+            text,
+            " ".repeat(right_pad),
+        );
+    }
+}
+
+/// Appends the line at an index with a label and right aligned value for an info section
+fn info_stat_line(frames: &mut [String], idx: usize, label: &str, value: impl ToString) {
+    if let Some(line) = frames.get_mut(idx) {
+        let value = value.to_string();
+        let pad = (INFO_WIDTH - 2).saturating_sub(label.len() + value.len());
+
+        *line = format!( 
+            "{}  │ {}{}{} │",
+            line,
+            label,
+            " ".repeat(pad),
+            value,
+        );
+    }
+}
+
+/// Gets the columns taken by a drawn board, two a cell and the edges
+fn board_columns(game: &GameState) -> usize {
+    2 * game.board.width() + 2
+}
+
+/// Gets the columns and lines taken by a drawn frame of a game
+fn frame_size(game: &GameState) -> (u16, u16) {
+    let columns = board_columns(game) + 2 + INFO_WIDTH + 2;
+    let lines = (game.rules.board.visible_rows() + 3).max(FRAME_HEIGHT);
+    (columns as u16, lines as u16)
+}
+
+/// Gets the top left corner of the game frame for a terminal size
+fn frame_origin(size: (u16, u16), game: &GameState) -> (u16, u16) {
+    let (columns, lines) = frame_size(game);
+    ((size.0 / 2).saturating_sub(columns / 2), (size.1 / 2).saturating_sub(lines / 2 + 3))
+}
+
+/// Gets the average number of pieces locked a second so far
+fn pieces_per_second(game: &GameState) -> f64 {
+    if game.tick == 0 {
+        return 0.0;
+    }
+    game.pieces as f64 * TICKS_PER_SECOND as f64 / game.tick as f64
+}
+
 /// Draws a frame of the game
-fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) -> Result<(), io::Error> {
+fn draw(out: &mut Stdout, game: &GameState, hud: &Hud, previous_frame: &mut Vec<String>) -> Result<(), io::Error> {
     // Terminal size
     let size = terminal::size().expect("Could not get terminal");
 
+    let frames = render_frame(game, hud);
+    draw_frame(out, &frames, frame_origin(size, game), previous_frame)?;
+
+    // flush term
+    out.flush()
+}
+
+/// Builds the lines of a frame of the game, board on the left and info on the right
+fn render_frame(game: &GameState, hud: &Hud) -> Vec<String> {
     // Create game frame
-    let mut frames: Vec<String> = vec![String::new(); 23];
+    let (_, lines) = frame_size(game);
+    let mut frames: Vec<String> = vec![String::new(); lines as usize];
+    let hidden = game.rules.board.hidden_rows;
+    let visible = game.rules.board.visible_rows();
+
+    // Info lines below the board are indented past it
+    for line in frames.iter_mut().skip(visible + 3) {
+        *line = " ".repeat(board_columns(game));
+    }
 
     // Draw top line
     if let Some(line) = frames.get_mut(1) {
         *line = format!(
             "{}{}{}",
             "┌",
-            "─".repeat(20),
+            "─".repeat(2 * game.board.width()),
             "┐"
         );
     }
 
     // Assemble frame
-    let shape = game.current_shape.get_shape(&game.rotation);
-    for y in 2..22 { // only render visible area
-        let frame = frames.get_mut(y).unwrap();
-        frame.push_str("│"); // Edge 
+    let shape = game.shape_cells(game.current_shape, game.rotation);
+    for y in hidden..game.board.height() { // only render visible area
+        let frame = frames.get_mut(y - hidden + 2).unwrap();
+        frame.push_str(&garbage_meter_tile(game, y)); // Edge doubles as the garbage meter
 
         // Render board pieces
-        for x in 0..10 {
+        for x in 0..game.board.width() {
             if game.board[y][x].is_block() {
                 *frame = format!("{}{}", frame, game.board[y][x].color_tile())
                 //frame.push_str("██"); 
@@ -224,43 +679,61 @@ fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) ->
         }
 
 
-        frame.push_str("│"); // edge
+        frame.push('│'); // edge
         //frames.push(frame);
     }
 
     // Bottom line
-    if let Some(line) = frames.get_mut(22) {
+    if let Some(line) = frames.get_mut(visible + 2) {
         *line = format!( 
             "└{}┘",
-            "─".repeat(20),
+            "─".repeat(2 * game.board.width()),
         );
     }
 
-    // Draw score box
+    // Draw score box, timed modes race the clock instead
+    let title = match game.rules.mode {
+        GameMode::Endless => " POINTS ",
+        GameMode::Sprint { .. } => " SPRINT ",
+        GameMode::Ultra { .. } => " ULTRA ",
+        GameMode::Dig { .. } => " DIG ",
+    };
     if let Some(line) = frames.get_mut(1) {
         *line = format!( 
-            "{}  ┌{}{}{}┐",
+            "{}  ┌{:─^INFO_WIDTH$}┐",
             line,
-            "─".repeat(4),
-            " POINTS ",
-            "─".repeat(4),
+            title,
         )
     }
-    if let Some(line) = frames.get_mut(2) {
-        let score = game.score.to_string();
-        let total_pad = INFO_WIDTH - score.len();
-        let left_pad = total_pad / 2;
-        let right_pad = total_pad - left_pad;
+    match game.rules.mode {
+        GameMode::Endless => {
+            info_text_line(&mut frames, 2, &game.score.to_string());
+            info_stat_line(&mut frames, 3, "LEVEL", game.level);
+            info_stat_line(&mut frames, 4, "LINES", game.lines);
+        },
+        GameMode::Sprint { lines } => {
+            info_text_line(&mut frames, 2, &storage::format_ticks(game.tick));
+            info_stat_line(&mut frames, 3, "LINES", format!("{}/{}", game.lines, lines));
+            info_stat_line(&mut frames, 4, "PPS", format!("{:.2}", pieces_per_second(game)));
+        },
+        GameMode::Ultra { .. } => {
+            info_text_line(&mut frames, 2, &storage::format_ticks(game.ticks_left().unwrap_or(0)));
+            info_stat_line(&mut frames, 3, "SCORE", game.score);
+            info_stat_line(&mut frames, 4, "LINES", game.lines);
+        },
+        GameMode::Dig { lines, .. } => {
+            info_text_line(&mut frames, 2, &storage::format_ticks(game.tick));
+            info_stat_line(&mut frames, 3, "DUG", format!("{}/{}", game.garbage_dug(), lines));
+            info_stat_line(&mut frames, 4, "PPS", format!("{:.2}", pieces_per_second(game)));
+        },
+    }
 
-        *line = format!( 
-            "{}  │{}{}{}│",
-            line,
-            " ".repeat(left_pad),
-            score,
-            " ".repeat(right_pad),
-        );
+    // Last clear, shown for a short while
+    match &hud.action {
+        Some((action, until)) if game.tick < *until => info_text_line(&mut frames, 5, action),
+        _ => info_padding_line(&mut frames, 5),
     }
-    if let Some(line) = frames.get_mut(3) {
+    if let Some(line) = frames.get_mut(6) {
         *line = format!( 
             "{}  └{}┘",
             line,
@@ -269,7 +742,7 @@ fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) ->
     }
 
     // Held shape
-    if let Some(line) = frames.get_mut(4) {
+    if let Some(line) = frames.get_mut(7) {
         *line = format!( 
             "{}  ┌{}{}{}┐",
             line,
@@ -278,18 +751,19 @@ fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) ->
             "─".repeat(5),
         )
     }
-    info_padding_line(&mut frames, 5);
-    let mut current_line = 6;
+    info_padding_line(&mut frames, 8);
+    let mut current_line = 9;
 
     // Draw held shape
     let shape = game.held;
     for x in 0..2 {
         if let Some(shape) = shape {
-            // Offset for o shape
-            let y = if let Shape::O = shape {1}else{0};
+            // Show the bottom two rows with anything in them
+            let cells = game.shape_cells(shape, Rotation::R0);
+            let y = cells.iter().rposition(|row| row.contains(&true)).unwrap() - 1;
 
             // Get line
-            let line = shape.get_shape(&Rotation::R0)[0+x+y];
+            let line = cells[x+y];
             let color = shape.get_color();
 
             // Convert line to str
@@ -343,11 +817,12 @@ fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) ->
     for shape_idx in 0..3 { // Iterate shape queue
         let shape = game.shape_queue[shape_idx];
         for x in 0..2 {
-            // Offset for o shape
-            let y = if let Shape::O = shape {1}else{0};
+            // Show the bottom two rows with anything in them
+            let cells = game.shape_cells(shape, Rotation::R0);
+            let y = cells.iter().rposition(|row| row.contains(&true)).unwrap() - 1;
 
             // Get line
-            let line = shape.get_shape(&Rotation::R0)[0+x+y];
+            let line = cells[x+y];
             let color = shape.get_color();
 
             // Convert line to str
@@ -381,10 +856,12 @@ fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) ->
         )
     }
 
-    // Get size of play area
-    let play_size = 20;
+    frames
+}
 
-    // Draw frame lines
+/// Draws the lines of a frame with its top left corner at a position
+fn draw_frame(out: &mut Stdout, frames: &[String], (left, top): (u16, u16), previous_frame: &mut Vec<String>) -> Result<(), io::Error> {
+    previous_frame.resize(frames.len(), String::new());
     for (y, frame) in frames.iter().enumerate() {
         // Only draw different lines
         if previous_frame.get(y) == Some(frame) {
@@ -392,61 +869,413 @@ fn draw(out: &mut Stdout, game: &GameState, previous_frame: &mut Vec<String>) ->
         }
 
         // Draw
-        out.queue(cursor::MoveTo(
-            (size.0 / 2) - play_size as u16,
-            (y as u16) + (size.1/2) - 15
-        ))?;
+        out.queue(cursor::MoveTo(left, top + y as u16))?;
         out.queue(style::Print(frame))?;
 
         // Update previous
         previous_frame[y] = frame.clone()
     }
 
-    // flush term
-    out.flush()
+    Ok(())
 }
 
-/// Waits for player input to determine next action
-fn game_over_update(game: &mut GameState, out: &mut Stdout) -> Result<(), io::Error> {
-    // Draw game over box
-    // ┌───┐
-    // │   │
-    // └───┘
+/// Gets a display name for a key
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_uppercase().to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Gets a display name for a button
+fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::Left => "Move left",
+        Button::Right => "Move right",
+        Button::SoftDrop => "Soft drop",
+        Button::HardDrop => "Hard drop",
+        Button::RotateCw => "Rotate clockwise",
+        Button::RotateCcw => "Rotate counter clockwise",
+        Button::Hold => "Hold",
+    }
+}
+
+/// Draws a titled box of centered lines in the middle of the screen
+fn draw_box(out: &mut Stdout, title: &str, lines: &[String]) -> Result<(), io::Error> {
+    // Fit the widest line with some padding
+    let width = lines.iter()
+        .map(|line| line.chars().count())
+        .chain([title.chars().count() + 2])
+        .max()
+        .unwrap_or(0) + 4;
+
+    // ┌─ title ─┐
+    // │  line   │
+    // └─────────┘
     let mut frames: Vec<String> = vec![];
-    frames.push(format!("┌{}┐", "─".repeat(26)));
-    frames.push("│        Gameover          │".to_string());
-    frames.push("│   Press Ctrl+C to exit   │".to_string());
-    frames.push("│ Any other key to restart │".to_string());
-    frames.push(format!("└{}┘", "─".repeat(26)));
-
-    let size = terminal::size().unwrap();
-    let x = size.0/2 - 13;
-    let y = size.1/2 - 2;
-    out.queue(MoveTo(x,y))?;
+    frames.push(format!("┌{:─^width$}┐", format!(" {title} ")));
+    for line in lines {
+        frames.push(format!("│{line:^width$}│"));
+    }
+    frames.push(format!("└{}┘", "─".repeat(width)));
+
+    let size = terminal::size()?;
+    let x = (size.0 / 2).saturating_sub(width as u16 / 2 + 1);
+    let y = (size.1 / 2).saturating_sub(frames.len() as u16 / 2);
     for (i, frame) in frames.iter().enumerate() {
-        out.queue(MoveTo(x,y+i as u16))?;
+        out.queue(MoveTo(x, y + i as u16))?;
         out.queue(Print(frame))?;
     }
 
+    out.flush()
+}
 
-    // Input
+/// Reads the next key press, ignoring releases and other events
+fn read_key_press() -> Result<Option<KeyEvent>, io::Error> {
     while poll(time::Duration::from_secs(0))? {
-        match read()? {
-            Event::Key(evt) => {
-                // Control + c
-                if evt.code == KeyCode::Char('c') 
-                    && evt.modifiers.contains(KeyModifiers::CONTROL)
-                {
-                    clean(); // Clean and exit game
-                }
+        if let Event::Key(evt) = read()? {
+            // Control + c
+            if evt.code == KeyCode::Char('c') 
+                && evt.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                clean(); // Clean and exit game
+            }
+
+            if !evt.kind.is_release() {
+                return Ok(Some(evt));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Shows the pause menu and handles choosing an entry
+fn pause_update(game: &mut GameState, selected: &mut usize, options: &Options, out: &mut Stdout) -> Result<(), io::Error> {
+    // Draw menu with the selection marked
+    let mut lines = vec![String::new()];
+    for (i, entry) in PAUSE_MENU.iter().enumerate() {
+        if i == *selected {
+            lines.push(format!("> {entry} <"));
+        } else {
+            lines.push(format!("  {entry}  ")); // Same width as selected
+        }
+    }
+    lines.push(String::new());
+    draw_box(out, "PAUSED", &lines)?;
+
+    // Input
+    while let Some(evt) = read_key_press()? {
+        match evt.code {
+            KeyCode::Up => *selected = (*selected + PAUSE_MENU.len() - 1) % PAUSE_MENU.len(),
+            KeyCode::Down => *selected = (*selected + 1) % PAUSE_MENU.len(),
+            code if PAUSE_KEYS.contains(&code) => game.resume(),
+            KeyCode::Enter => match PAUSE_MENU[*selected] {
+                "Resume" => game.resume(),
+                "Restart" => *game = options.new_game(),
+                "Controls" => game.game_phase = GamePhase::Help,
+                _ => clean(),
+            },
+            _ => {},
+        }
+
+        // Stop reading once the menu has closed
+        if game.game_phase != GamePhase::Paused {
+            *selected = 0;
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Shows the current key bindings until a key is pressed
+fn help_update(game: &mut GameState, out: &mut Stdout) -> Result<(), io::Error> {
+    // One line per binding
+    let mut lines = vec![String::new()];
+    for (key, button) in KEY_BINDINGS {
+        lines.push(format!("{:<26}{:>6}", button_name(button), key_name(key)));
+    }
+    let pause_keys: Vec<String> = PAUSE_KEYS.iter().map(|key| key_name(*key)).collect();
+    lines.push(format!("{:<24}{:>8}", "Pause", pause_keys.join(" / ")));
+    lines.push(format!("{:<24}{:>8}", "Quit", "Ctrl+C"));
+    lines.push(String::new());
+    lines.push("Press any key to go back".to_string());
+    draw_box(out, "CONTROLS", &lines)?;
+
+    // Any key returns to the pause menu
+    if read_key_press()?.is_some() {
+        game.game_phase = GamePhase::Paused;
+    }
+
+    Ok(())
+}
+
+/// High scores for the game over and score screens
+struct Scoreboard {
+    scores: HighScores,
+    path: Option<PathBuf>,
+    /// Name being typed while a qualifying score is entered
+    name: Option<String>,
+    /// Problem loading or saving scores
+    error: Option<String>,
+    /// Index of the mode whose table is shown
+    mode: usize,
+    /// Record of a timed mode from before the game
+    best: Option<PersonalBest>,
+    /// Whether the game set a new record
+    new_best: bool,
+}
+
+impl Scoreboard {
+    /// Creates an empty scoreboard that isn't backed by a file
+    fn new() -> Self {
+        Scoreboard {
+            scores: HighScores::default(),
+            path: None,
+            name: None,
+            error: None,
+            mode: 0,
+            best: None,
+            new_best: false,
+        }
+    }
+
+    /// Loads the high score file for a finished game, asking for a name if it qualifies
+    fn load(game: &GameState) -> Self {
+        let mut board = Scoreboard::new();
+        board.path = HighScores::default_path();
+
+        match &board.path {
+            Some(path) => match HighScores::load(path) {
+                Ok(scores) => board.scores = scores,
+                Err(err) => board.error = Some(format!("Could not load scores: {err}")),
+            },
+            None => board.error = Some("No data directory for scores".to_string()),
+        }
 
-                // Any other key restarts
-                *game = GameState::new();
-                out.queue(Clear(ClearType::All))?;
+        // Only games on the standard board with the standard pieces are ranked
+        let standard = Ruleset::default();
+        if game.rules.board != standard.board
+            || game.rules.randomizer != standard.randomizer
+            || game.rules.rotation_system != standard.rotation_system
+        {
+            return board;
+        }
+
+        // Timed modes only count finished games
+        let timed = game.rules.mode.ranks_by_time();
+        if timed && !game.completed {
+            return board;
+        }
+        if timed {
+            board.submit_best(game);
+        }
+
+        // Only ask for a name when the score can be kept
+        let entry = HighScore::from_game("", game);
+        if board.error.is_none() && (timed || game.score > 0) && board.scores.qualifies(&entry) {
+            board.name = Some(String::new());
+        }
+        board
+    }
+
+    /// Records a finished timed game if it beats the personal best
+    fn submit_best(&mut self, game: &GameState) {
+        let Some(path) = PersonalBests::default_path() else {
+            return;
+        };
+        let mut bests = match PersonalBests::load(&path) {
+            Ok(bests) => bests,
+            Err(err) => {
+                self.error = Some(format!("Could not load personal bests: {err}"));
+                return;
+            },
+        };
+
+        self.best = bests.get(&game.rules.mode.name()).cloned();
+        self.new_best = bests.submit(PersonalBest::from_game(game));
+        if self.new_best
+            && let Err(err) = bests.save(&path)
+        {
+            self.error = Some(format!("Could not save personal bests: {err}"));
+        }
+    }
+
+    /// Summarises a finished game of a mode with a goal
+    fn result_lines(&self, game: &GameState) -> Vec<String> {
+        let progress = match game.rules.mode {
+            GameMode::Endless => return vec![],
+            GameMode::Sprint { lines } => format!("{}/{lines} lines", game.lines),
+            GameMode::Ultra { .. } => {
+                let result = format!("Score {}  Lines {}", game.score, game.lines);
+                return vec![result, String::new()];
             },
+            GameMode::Dig { lines, .. } => format!("{}/{lines} garbage", game.garbage_dug()),
+        };
+        if !game.completed {
+            return vec![format!("Did not finish ({progress})"), String::new()];
+        }
+
+        let mut result = vec![format!("Time {}  PPS {:.2}", storage::format_ticks(game.tick), pieces_per_second(game))];
+        match &self.best {
+            _ if self.new_best => result.push("New personal best!".to_string()),
+            Some(best) => result.push(format!(
+                "Best {} ({})",
+                storage::format_ticks(best.ticks),
+                storage::format_delta(game.tick as i64 - best.ticks as i64),
+            )),
+            None => {},
+        }
+        result.push(String::new());
+        result
+    }
+
+    /// Saves the game under the typed name
+    fn save(&mut self, game: &GameState) {
+        let name = self.name.take().unwrap_or_default();
+        self.scores.insert(HighScore::from_game(&name, game));
+
+        if let Some(path) = &self.path
+            && let Err(err) = self.scores.save(path)
+        {
+            self.error = Some(format!("Could not save scores: {err}"));
+        }
+    }
+
+    /// Gets every mode that can be shown, the current game's first
+    fn modes(&self, game: &GameState) -> Vec<String> {
+        let mut modes = vec![game.rules.mode.name()];
+        for mode in self.scores.modes() {
+            if !modes.contains(&mode) {
+                modes.push(mode);
+            }
+        }
+        modes
+    }
+}
+
+/// Saves a finished game's replay, returning a message saying where to
+fn save_replay(game: &GameState) -> String {
+    let Some(dir) = Replay::default_dir() else {
+        return "No data directory for replays".to_string();
+    };
+
+    let name = format!("{}-{}.jtr", storage::unix_time(), game.seed);
+    match Replay::from_game(game).save(&dir.join(&name)) {
+        Ok(()) => format!("Replay saved as {name}"),
+        Err(err) => format!("Could not save replay: {err}"),
+    }
+}
+
+/// Waits for player input to determine next action
+fn game_over_update(game: &mut GameState, scoreboard: &mut Scoreboard, replay_message: &str, options: &Options, out: &mut Stdout) -> Result<(), io::Error> {
+    // Qualifying scores get a name first
+    if let Some(name) = &mut scoreboard.name {
+        let result = if game.rules.mode.ranks_by_time() {
+            format!("Time {}", storage::format_ticks(game.tick))
+        } else {
+            format!("Score {}", game.score)
+        };
+        let lines = [
+            result,
+            String::new(),
+            format!("Name: {:<width$}", format!("{name}_"), width = MAX_NAME_LEN + 1),
+            String::new(),
+            "Enter to save, Esc to skip".to_string(),
+        ];
+        draw_box(out, "NEW HIGH SCORE", &lines)?;
+
+        while let Some(evt) = read_key_press()? {
+            match evt.code {
+                KeyCode::Enter => {
+                    scoreboard.save(game);
+                    game.game_phase = GamePhase::Score;
+                    break;
+                },
+                KeyCode::Esc => {
+                    scoreboard.name = None;
+                    out.queue(Clear(ClearType::All))?;
+                    break;
+                },
+                KeyCode::Backspace => {
+                    name.pop();
+                },
+                // Leading spaces are most likely hard drops left over from the game
+                KeyCode::Char(' ') if name.is_empty() => {},
+                KeyCode::Char(c) if name.chars().count() < MAX_NAME_LEN && !c.is_control() => name.push(c),
+                _ => {},
+            }
+        }
+        return Ok(());
+    }
 
-            // Ignore other events
-            _ => ()
+    let mut lines = scoreboard.result_lines(game);
+    lines.extend([
+        "Press Ctrl+C to exit".to_string(),
+        "H to view high scores".to_string(),
+        "Any other key to restart".to_string(),
+        format!("Seed {}", game.seed),
+        replay_message.to_string(),
+    ]);
+    if let Some(error) = &scoreboard.error {
+        lines.push(error.clone());
+    }
+    draw_box(out, "Gameover", &lines)?;
+
+    // Any other key restarts
+    if let Some(evt) = read_key_press()? {
+        if evt.code == KeyCode::Char('h') {
+            game.game_phase = GamePhase::Score;
+        } else {
+            *game = options.new_game();
+        }
+    }
+    Ok(())
+}
+
+/// Shows the top scores of each mode until a key is pressed
+fn score_update(game: &mut GameState, scoreboard: &mut Scoreboard, options: &Options, out: &mut Stdout) -> Result<(), io::Error> {
+    let modes = scoreboard.modes(game);
+    let mode = &modes[scoreboard.mode % modes.len()];
+
+    // Table, always full height so switching modes overwrites every row
+    let header = format!("{:>3} {:<12} {:>8} {:>5} {:>3} {:>9} {:>10}", "#", "NAME", "SCORE", "LINES", "LV", "TIME", "DATE");
+    let mut lines = vec![format!("< {mode} >"), String::new(), header.clone()];
+    let top = scoreboard.scores.top(mode);
+    for rank in 0..TABLE_SIZE {
+        lines.push(match top.get(rank) {
+            Some(entry) => format!(
+                "{:>3} {:<12} {:>8} {:>5} {:>3} {:>9} {:>10}",
+                format!("{}.", rank + 1),
+                entry.name,
+                entry.score,
+                entry.lines,
+                entry.level,
+                storage::format_ticks(entry.ticks),
+                storage::format_date(entry.date),
+            ),
+            None => format!("{:>3} {:<width$}", format!("{}.", rank + 1), "-", width = header.len() - 4),
+        });
+    }
+    lines.push(String::new());
+    lines.push("←/→ to change mode, any other key to play again".to_string());
+    draw_box(out, "HIGH SCORES", &lines)?;
+
+    // Input
+    while let Some(evt) = read_key_press()? {
+        match evt.code {
+            KeyCode::Left => scoreboard.mode = (scoreboard.mode + modes.len() - 1) % modes.len(),
+            KeyCode::Right => scoreboard.mode = (scoreboard.mode + 1) % modes.len(),
+            _ => {
+                *game = options.new_game();
+                break;
+            },
         }
     }
     Ok(())
@@ -454,33 +1283,87 @@ fn game_over_update(game: &mut GameState, out: &mut Stdout) -> Result<(), io::Er
 
 /// Program entry point
 fn main() -> Result<(), io::Error> {
-    let mut state = setup(); // Set up game
-    let frame_time = Duration::from_secs_f64(1.0 / 24.0);
+    let options = Options::from_args();
+    match &options.command {
+        Command::Play => {},
+        Command::Replay(path) => return tui::replay::run(path),
+        Command::Versus { bot } => return tui::versus::run(&options, *bot),
+        Command::Demo => return tui::bot::run(&options, tui::bot::Player::Builtin(Bot::new(options.bot_pps))),
+        Command::Host(port) => return tui::netplay::run(host(*port, &options)),
+        Command::Join(addr) => return tui::netplay::run(join(addr)),
+        Command::Tbp(command) => return tui::bot::run(&options, tui::bot::Player::external(command)),
+    }
+
+    // Listen for spectators before the terminal is taken over, so errors can be printed
+    let mut spectators = options.spectate_port.map(|port| {
+        SpectatorServer::bind(port).unwrap_or_else(|err| {
+            eprintln!("error: could not serve spectators on port {port}: {err}");
+            std::process::exit(1);
+        })
+    });
+
+    setup(); // Set up game
+    let mut state = options.new_game();
+    let frame_time = Duration::from_secs(1) / TICKS_PER_SECOND;
     let mut out = stdout();
+    let mut clock = TickClock::new();
+    let mut inputs: Vec<Input> = vec![];
+    let mut hud = Hud::for_game(&state);
+    let mut keyboard = Keyboard::new();
 
-    let mut previous_frame: Vec<String> = vec![String::new(); 23];
+    let mut previous_frame: Vec<String> = vec![];
 
     // Enter game loop
+    let mut shown_phase = state.game_phase;
+    let mut selected = 0;
+    let mut scoreboard = Scoreboard::new();
+    let mut replay_message = String::new();
     loop {
-        if let GamePhase::Playing = state.game_phase { // Playing
-            // Get current time
-            let start = Instant::now();
-
-            // Game logic
-            draw(&mut out, &state, &mut previous_frame)?; // Draw game
-            update(&mut state)?; // Update game
-
-            // Wait for frame
-            let elapsed = start.elapsed();
-            if elapsed < frame_time {
-                sleep(frame_time - elapsed);
-            }
-        } else if let GamePhase::GameOver = state.game_phase { // Game over
-            // Game over screen
-            previous_frame = vec![String::new(); 23]; // Reset frames to avoid printing
+        // Every screen starts from a blank terminal
+        if state.game_phase != shown_phase {
+            shown_phase = state.game_phase;
+            out.queue(Clear(ClearType::All))?;
+            previous_frame.clear(); // Reset frames to avoid printing
             // bug
-            game_over_update(&mut state, &mut out)?; // Game over update screen
-            // TODO: Score screen?
+            clock.reset(); // Don't catch up on time spent off the board
+            inputs.clear();
+            keyboard = Keyboard::new();
+
+            // Fresh hud for a new game
+            if state.tick == 0 {
+                hud = Hud::for_game(&state);
+            }
+
+            // Finished games go on the high score table
+            if state.game_phase == GamePhase::GameOver {
+                scoreboard = Scoreboard::load(&state);
+                replay_message = save_replay(&state);
+            }
+        }
+
+        // Get current time
+        let start = Instant::now();
+
+        match state.game_phase {
+            GamePhase::Playing => {
+                // Game logic
+                draw(&mut out, &state, &hud, &mut previous_frame)?; // Draw game
+                update(&mut state, &mut hud, &mut clock, &mut keyboard, &mut inputs)?; // Update game
+            },
+            GamePhase::Paused => pause_update(&mut state, &mut selected, &options, &mut out)?,
+            GamePhase::Help => help_update(&mut state, &mut out)?,
+            GamePhase::GameOver => game_over_update(&mut state, &mut scoreboard, &replay_message, &options, &mut out)?,
+            GamePhase::Score => score_update(&mut state, &mut scoreboard, &options, &mut out)?,
+        }
+
+        if let Some(server) = &mut spectators {
+            server.broadcast(&state);
+        }
+
+        // Wait for frame
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            sleep(frame_time - elapsed);
         }
     }
 }
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use crate::{
+    board::{self, Board},
+    game_state::{classify_tspin, Button, Coord},
+    rotation_system::RotationSystem,
+    scoring::TSpin,
+    shapes::{Rotation, Shape},
+};
+
+/// A resting place a piece can reach, with the buttons that get it there
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Placement {
+    pub rotation: Rotation,
+    /// Top left of the piece's box, like `GameState::player_pos`
+    pub pos: Coord,
+    /// Kick index if a T piece rotated into place and didn't fall after, for classifying spins
+    pub kick: Option<usize>,
+    /// How a T piece is spun into place, always none for other pieces
+    pub spin: TSpin,
+    /// Pressed in order, ending with the hard drop that locks the piece
+    pub buttons: Vec<Button>,
+}
+
+/// Gets every resting place a piece can reach from a position, by the fewest buttons
+///
+/// Searches moves, rotations with their kicks and single row soft drops, so
+/// tucks and spins are found. Each placement is a distinct rotation and
+/// position, reached by the shortest sequence of presses that ends with a
+/// hard drop. A T piece gets the best spin that reaches it instead, by the
+/// shortest sequence for that spin, as spins score more. Nothing is found if
+/// the piece doesn't fit where it starts.
+pub fn placements(board: &Board, rotation_system: RotationSystem, shape: Shape, rotation: Rotation, pos: Coord) -> Vec<Placement> {
+    // Kicks are kept apart while searching a T, spins depend on the last one
+    type State = (i16, i16, Rotation, Option<usize>);
+    let spins = shape == Shape::T;
+    let boxes = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270]
+        .map(|rotation| rotation_system.cells(shape, rotation));
+    let masks = boxes.map(|cells| board::piece_mask(&cells));
+    let fits = |rotation: Rotation, x: i16, y: i16| !board.collides(masks[rotation as usize], x, y);
+    if !fits(rotation, pos.x, pos.y) {
+        return vec![];
+    }
+
+    // Every state a box that fits could be in gets a slot, its left column and
+    // top row can be up to 3 off the board and a T has 5 kicks or none
+    let (columns, rows) = (board.width() + 3, board.height() + 3);
+    let kick_slots = if spins { 6 } else { 1 };
+    let index = |(x, y, rotation, kick): State| {
+        let layer = rotation as usize * kick_slots + kick.map_or(0, |kick| kick + 1);
+        (layer * rows + (y + 3) as usize) * columns + (x + 3) as usize
+    };
+
+    // Breadth first, so the first path found to anything is the shortest
+    let start: State = (pos.x, pos.y, rotation, None);
+    let mut paths: Vec<Option<(Option<State>, Button)>> = vec![None; 4 * kick_slots * rows * columns];
+    paths[index(start)] = Some((None, Button::HardDrop));
+    let mut queue = VecDeque::from([start]);
+    // Where each resting place went in the found list
+    let mut rested: Vec<Option<usize>> = vec![None; 4 * rows * columns];
+    let mut found: Vec<Placement> = vec![];
+
+    while let Some(state) = queue.pop_front() {
+        let (x, y, rotation, kick) = state;
+
+        // Hard drop from here, a piece that falls loses its kick
+        let drop_y = board.drop_y(masks[rotation as usize], x, y);
+        let kick = if drop_y == y { kick } else { None };
+        let spin = match kick {
+            Some(kick) => classify_tspin(board, &boxes[rotation as usize], &Coord { x, y: drop_y }, kick),
+            None => TSpin::None,
+        };
+        let rest = (rotation as usize * rows + (drop_y + 3) as usize) * columns + (x + 3) as usize;
+        if rested[rest].is_none_or(|i| spin > found[i].spin) {
+            let mut buttons = vec![Button::HardDrop];
+            let mut at = state;
+            while let Some((Some(parent), button)) = paths[index(at)] {
+                buttons.push(button);
+                at = parent;
+            }
+            buttons.reverse();
+            let placement = Placement { rotation, pos: Coord { x, y: drop_y }, kick, spin, buttons };
+            match rested[rest] {
+                Some(i) => found[i] = placement,
+                None => {
+                    rested[rest] = Some(found.len());
+                    found.push(placement);
+                },
+            }
+        }
+
+        // Moves on from here
+        let mut next = [None; 5];
+        if fits(rotation, x - 1, y) {
+            next[0] = Some(((x - 1, y, rotation, None), Button::Left));
+        }
+        if fits(rotation, x + 1, y) {
+            next[1] = Some(((x + 1, y, rotation, None), Button::Right));
+        }
+        if fits(rotation, x, y + 1) {
+            next[2] = Some(((x, y + 1, rotation, None), Button::SoftDrop));
+        }
+        for (slot, to, button) in [(3, rotation.rotate_cw(), Button::RotateCw), (4, rotation.rotate_ccw(), Button::RotateCcw)] {
+            if let Some((pos, kick)) = rotation_system.rotate(board, shape, rotation, to, &Coord { x, y }) {
+                next[slot] = Some(((pos.x, pos.y, to, spins.then_some(kick)), button));
+            }
+        }
+
+        for (to, button) in next.into_iter().flatten() {
+            let path = &mut paths[index(to)];
+            if path.is_none() {
+                *path = Some((Some(state), button));
+                queue.push_back(to);
+            }
+        }
+    }
+
+    found
+}
@@ -0,0 +1,329 @@
+use std::{collections::VecDeque, io::{self, Read, Write}, net::{TcpListener, TcpStream, ToSocketAddrs}, time::Duration};
+
+use crate::{
+    game_state::{GameState, Input},
+    replay::{self, Reader, Replay},
+    rules::Ruleset,
+    scoring::GameEvent,
+};
+
+/// Port games are hosted on when none is given
+pub const DEFAULT_PORT: u16 = 7474;
+
+/// Ticks between a local input and the tick it is applied on, hiding the round trip
+pub const INPUT_DELAY: usize = 4;
+
+/// How long joining waits for the host to start the game
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Message kinds, the first byte of every message
+const HELLO: u8 = 1;
+const INPUTS: u8 = 2;
+const BYE: u8 = 3;
+
+/// A message between the two players
+///
+/// On the wire every message is prefixed with its length as a varint.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Message {
+    /// Sent by the host to start the game, with the seed and rules both boards use
+    Hello { seed: u64, rules: Ruleset },
+
+    /// Everything a player did on a tick
+    Inputs { tick: u64, inputs: Vec<Input> },
+
+    /// The player left
+    Bye,
+}
+
+impl Message {
+    /// Encodes the message without its length
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            // The seed and rules are sent as an empty replay, which is versioned
+            Message::Hello { seed, rules } => {
+                let replay = Replay { seed: *seed, rules: rules.clone(), inputs: vec![], length: 0 };
+                let mut bytes = vec![HELLO];
+                bytes.extend(replay.encode());
+                bytes
+            },
+            Message::Inputs { tick, inputs } => {
+                let mut bytes = vec![INPUTS];
+                replay::put_varint(&mut bytes, *tick);
+                replay::put_varint(&mut bytes, inputs.len() as u64);
+                for input in inputs {
+                    replay::put_input(&mut bytes, *input);
+                }
+                bytes
+            },
+            Message::Bye => vec![BYE],
+        }
+    }
+
+    /// Decodes a message without its length
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let message = match reader.byte()? {
+            HELLO => {
+                let replay = Replay::decode(&bytes[1..])?;
+                return Ok(Message::Hello { seed: replay.seed, rules: replay.rules });
+            },
+            INPUTS => {
+                let tick = reader.varint()?;
+                let count = reader.varint()?;
+                let mut inputs = vec![];
+                for _ in 0..count {
+                    inputs.push(reader.input()?);
+                }
+                Message::Inputs { tick, inputs }
+            },
+            BYE => Message::Bye,
+            _ => return Err(replay::invalid("unknown message")),
+        };
+
+        if !reader.is_empty() {
+            return Err(replay::invalid("message is too long"));
+        }
+        Ok(message)
+    }
+
+    /// Encodes the message with its length in front
+    fn frame(&self) -> Vec<u8> {
+        let message = self.encode();
+        let mut bytes = vec![];
+        replay::put_varint(&mut bytes, message.len() as u64);
+        bytes.extend(message);
+        bytes
+    }
+
+    /// Removes the first whole message from the front of some bytes, if there is one
+    fn take(bytes: &mut Vec<u8>) -> io::Result<Option<Self>> {
+        // Length, which can itself be cut off
+        let mut len = 0;
+        let mut header = 0;
+        loop {
+            let Some(byte) = bytes.get(header) else {
+                return Ok(None);
+            };
+            if header >= 4 {
+                return Err(replay::invalid("message is too long"));
+            }
+            len |= ((byte & 0x7f) as usize) << (7 * header);
+            header += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        if bytes.len() < header + len {
+            return Ok(None);
+        }
+        let message = Message::decode(&bytes[header..header + len]);
+        bytes.drain(..header + len);
+        message.map(Some)
+    }
+}
+
+/// A game against another player over TCP, kept in sync by exchanging inputs
+///
+/// Both players simulate both boards. Each tick only runs once both players'
+/// inputs for it have arrived, so the boards never disagree. Local inputs are
+/// scheduled `INPUT_DELAY` ticks ahead to give them time to arrive.
+pub struct Session {
+    stream: TcpStream,
+    /// Bytes received that don't make a whole message yet
+    received: Vec<u8>,
+    /// Bytes waiting for room to be sent
+    outgoing: Vec<u8>,
+    /// The local player's board first, then the opponent's
+    pub games: [GameState; 2],
+    /// Inputs each board will apply on its coming ticks, in order
+    queues: [VecDeque<Vec<Input>>; 2],
+    /// Garbage sent to each board, arriving on its next tick
+    garbage: [Vec<Input>; 2],
+    /// Tick the next local inputs are sent for
+    sent_tick: u64,
+    /// Tick the next opponent inputs are expected for
+    received_tick: u64,
+    /// Set once the opponent has left or the connection has dropped
+    pub opponent_left: bool,
+}
+
+impl Session {
+    /// Waits for an opponent to connect, then starts a game with them
+    pub fn host(listener: &TcpListener, seed: u64, rules: Ruleset) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        let mut session = Session::new(stream, seed, rules.clone())?;
+        session.send(&Message::Hello { seed, rules })?;
+        Ok(session)
+    }
+
+    /// Connects to a host and starts the game it sends
+    pub fn join(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+
+        // Read a byte at a time so nothing after the hello is lost
+        let mut bytes = vec![];
+        let hello = loop {
+            let mut byte = [0];
+            stream.read_exact(&mut byte)?;
+            bytes.push(byte[0]);
+            if let Some(message) = Message::take(&mut bytes)? {
+                break message;
+            }
+        };
+
+        match hello {
+            Message::Hello { seed, rules } => Session::new(stream, seed, rules),
+            _ => Err(replay::invalid("host didn't start a game")),
+        }
+    }
+
+    /// Starts a session over a connected stream
+    fn new(stream: TcpStream, seed: u64, rules: Ruleset) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+
+        // The first ticks have no inputs, they are what the delay hides
+        let delay: VecDeque<Vec<Input>> = vec![vec![]; INPUT_DELAY].into();
+        Ok(Session {
+            stream,
+            received: vec![],
+            outgoing: vec![],
+            games: [GameState::with_rules(seed, rules.clone()), GameState::with_rules(seed, rules)],
+            queues: [delay.clone(), delay],
+            garbage: [vec![], vec![]],
+            sent_tick: INPUT_DELAY as u64,
+            received_tick: INPUT_DELAY as u64,
+            opponent_left: false,
+        })
+    }
+
+    /// Sends the local inputs for the next tick
+    ///
+    /// Returns false without sending if the opponent is too far behind to
+    /// take more, the inputs should be sent again later.
+    pub fn send_inputs(&mut self, inputs: &[Input]) -> io::Result<bool> {
+        if self.queues[0].len() > INPUT_DELAY {
+            return Ok(false);
+        }
+
+        self.send(&Message::Inputs { tick: self.sent_tick, inputs: inputs.to_vec() })?;
+        self.queues[0].push_back(inputs.to_vec());
+        self.sent_tick += 1;
+        Ok(true)
+    }
+
+    /// Reads every message that has arrived from the opponent
+    pub fn receive(&mut self) -> io::Result<()> {
+        self.flush()?;
+
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    self.opponent_left = true;
+                    break;
+                },
+                Ok(len) => self.received.extend_from_slice(&buffer[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) if is_disconnect(&err) => {
+                    self.opponent_left = true;
+                    break;
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        while let Some(message) = Message::take(&mut self.received)? {
+            match message {
+                Message::Inputs { tick, inputs } => {
+                    if tick != self.received_tick {
+                        return Err(replay::invalid("inputs arrived out of order"));
+                    }
+                    self.queues[1].push_back(inputs);
+                    self.received_tick += 1;
+                },
+                Message::Bye => self.opponent_left = true,
+                Message::Hello { .. } => return Err(replay::invalid("game was already started")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Advances both boards by a tick if both players' inputs for it are known
+    ///
+    /// Attacks from each board are sent to the other, arriving on its next tick.
+    /// Both boards step before either's attacks are delivered, so each player's
+    /// machine sees the same timing whichever board it keeps first.
+    pub fn step(&mut self) -> bool {
+        if self.queues.iter().any(|queue| queue.is_empty()) {
+            return false;
+        }
+
+        let mut attacks: [Vec<Input>; 2] = [vec![], vec![]];
+        for i in 0..2 {
+            let mut inputs = self.queues[i].pop_front().unwrap_or_default();
+            inputs.append(&mut self.garbage[i]);
+
+            // Only look at the events from this tick, the front end takes them later
+            let seen = self.games[i].events.len();
+            self.games[i].step(&inputs);
+            for event in &self.games[i].events[seen..] {
+                if let GameEvent::Attack(lines) = event {
+                    attacks[1 - i].push(Input::Garbage(*lines));
+                }
+            }
+        }
+
+        for (garbage, attacks) in self.garbage.iter_mut().zip(attacks) {
+            garbage.extend(attacks);
+        }
+        true
+    }
+
+    /// Tells the opponent the player is leaving
+    pub fn quit(&mut self) {
+        let _ = self.send(&Message::Bye);
+    }
+
+    /// Queues a message and sends as much as the connection will take
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        self.outgoing.extend(message.frame());
+        self.flush()
+    }
+
+    /// Sends as much of the outgoing bytes as the connection will take
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => {
+                    self.opponent_left = true;
+                    break;
+                },
+                Ok(len) => {
+                    self.outgoing.drain(..len);
+                },
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) if is_disconnect(&err) => {
+                    self.opponent_left = true;
+                    self.outgoing.clear();
+                    break;
+                },
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Determines if an error means the other end of the connection has gone
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted,
+    )
+}
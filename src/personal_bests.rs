@@ -0,0 +1,120 @@
+use std::{io, path::{Path, PathBuf}};
+
+use crate::{game_state::GameState, storage};
+
+/// First line of a personal best file, bumped if the format changes
+const HEADER: &str = "# jordtris personal bests v1";
+
+/// The fastest finished game of a timed mode
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PersonalBest {
+    /// Name of the mode the game was played in
+    pub mode: String,
+    /// Time taken in ticks
+    pub ticks: u64,
+    pub pieces: u32,
+    /// Tick each multiple of `SPLIT_LINES` lines was reached on
+    pub splits: Vec<u64>,
+    /// When the game finished, in seconds since the unix epoch
+    pub date: u64,
+    pub seed: u64,
+}
+
+impl PersonalBest {
+    /// Creates a record of a finished game
+    pub fn from_game(game: &GameState) -> Self {
+        PersonalBest {
+            mode: game.rules.mode.name(),
+            ticks: game.tick,
+            pieces: game.pieces,
+            splits: game.splits.clone(),
+            date: storage::unix_time(),
+            seed: game.seed,
+        }
+    }
+
+    /// Gets how far ahead (negative) or behind (positive) a tick is at a split, in ticks
+    pub fn split_delta(&self, split: usize, tick: u64) -> Option<i64> {
+        let best = *self.splits.get(split)?;
+        Some(tick as i64 - best as i64)
+    }
+
+    /// Writes the record as a line of the personal best file
+    fn to_line(&self) -> String {
+        let splits: Vec<String> = self.splits.iter().map(|split| split.to_string()).collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.mode, self.ticks, self.pieces,
+            splits.join(","), self.date, self.seed,
+        )
+    }
+
+    /// Reads a record from a line of the personal best file
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let mode = fields.next()?.to_string();
+        let ticks = fields.next()?.parse().ok()?;
+        let pieces = fields.next()?.parse().ok()?;
+
+        // Splits are comma separated, a game can end before the first
+        let splits = match fields.next()? {
+            "" => vec![],
+            splits => splits.split(',')
+                .map(|split| split.parse().ok())
+                .collect::<Option<Vec<u64>>>()?,
+        };
+
+        let record = PersonalBest {
+            mode,
+            ticks,
+            pieces,
+            splits,
+            date: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+        };
+        Some(record)
+    }
+}
+
+/// The best time of every timed mode
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PersonalBests {
+    pub entries: Vec<PersonalBest>,
+}
+
+impl PersonalBests {
+    /// Default location of the personal best file
+    pub fn default_path() -> Option<PathBuf> {
+        storage::data_file("personal_bests.tsv")
+    }
+
+    /// Loads a personal best file, a missing file has no records
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let entries = storage::load_lines(path, HEADER)?
+            .iter()
+            .filter_map(|line| PersonalBest::from_line(line))
+            .collect();
+        Ok(PersonalBests { entries })
+    }
+
+    /// Saves the records, creating the directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        storage::save_lines(path, HEADER, self.entries.iter().map(PersonalBest::to_line))
+    }
+
+    /// Gets the record of a mode
+    pub fn get(&self, mode: &str) -> Option<&PersonalBest> {
+        self.entries.iter().find(|entry| entry.mode == mode)
+    }
+
+    /// Keeps a record if it beats the mode's current one, returning whether it did
+    pub fn submit(&mut self, record: PersonalBest) -> bool {
+        if self.get(&record.mode).is_some_and(|best| best.ticks <= record.ticks) {
+            return false;
+        }
+
+        self.entries.retain(|entry| entry.mode != record.mode);
+        self.entries.push(record);
+        true
+    }
+}
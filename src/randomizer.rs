@@ -0,0 +1,202 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::shapes::Shape;
+
+/// Every piece, in the order `Shape::random` numbers them
+const SHAPES: [Shape; 7] = [Shape::I, Shape::J, Shape::L, Shape::O, Shape::Z, Shape::T, Shape::S];
+
+/// Pieces the TGM randomizer may start with, never one that forces an overhang
+const TGM_FIRST: [Shape; 4] = [Shape::I, Shape::J, Shape::L, Shape::T];
+
+/// Times the TGM randomizer rerolls a piece in its history before keeping it
+const TGM_REROLLS: u32 = 6;
+
+/// How the sequence of pieces is drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Randomizer {
+    /// Every piece once in each bag of 7, shuffled
+    SevenBag,
+
+    /// Every piece twice in each bag of 14, shuffled
+    FourteenBag,
+
+    /// Every piece equally likely every time
+    Random,
+
+    /// Rolls again once if the piece repeats the last one, like the NES game
+    Nes,
+
+    /// Rerolls up to 6 times for a piece not among the last 4, like TGM2
+    Tgm,
+
+    /// A bag of 7 with one random piece added, 8 a bag
+    BagPlusOne,
+}
+
+impl Randomizer {
+    /// Every randomizer, in the order replays number them
+    pub const ALL: [Randomizer; 6] = [
+        Randomizer::SevenBag,
+        Randomizer::FourteenBag,
+        Randomizer::Random,
+        Randomizer::Nes,
+        Randomizer::Tgm,
+        Randomizer::BagPlusOne,
+    ];
+
+    /// Short name of the randomizer, as given on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Randomizer::SevenBag => "7bag",
+            Randomizer::FourteenBag => "14bag",
+            Randomizer::Random => "random",
+            Randomizer::Nes => "nes",
+            Randomizer::Tgm => "tgm",
+            Randomizer::BagPlusOne => "bag+1",
+        }
+    }
+
+    /// Parses a randomizer from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Randomizer::ALL.into_iter().find(|randomizer| randomizer.name() == name)
+    }
+}
+
+/// Draws the pieces of one game from a seed
+///
+/// The same randomizer and seed always give the same pieces.
+#[derive(Clone, Debug)]
+pub struct PieceGenerator {
+    randomizer: Randomizer,
+    rng: StdRng,
+    /// Pieces left in the current bag, drawn from the back
+    bag: Vec<Shape>,
+    /// Most recent pieces drawn, newest last
+    history: Vec<Shape>,
+    /// Pieces drawn so far
+    drawn: u64,
+}
+
+impl PieceGenerator {
+    /// Creates a generator for a randomizer
+    pub fn new(randomizer: Randomizer, seed: u64) -> Self {
+        // TGM2 starts its history as if these had just been drawn
+        let history = match randomizer {
+            Randomizer::Tgm => vec![Shape::Z, Shape::S, Shape::S, Shape::Z],
+            _ => vec![],
+        };
+        PieceGenerator {
+            randomizer,
+            rng: StdRng::seed_from_u64(seed),
+            bag: vec![],
+            history,
+            drawn: 0,
+        }
+    }
+
+    /// Gets the randomizer pieces are drawn with
+    pub fn randomizer(&self) -> Randomizer {
+        self.randomizer
+    }
+
+    /// Draws the next piece
+    pub fn next_shape(&mut self) -> Shape {
+        let shape = match self.randomizer {
+            Randomizer::SevenBag => {
+                // The first piece comes before the first bag, as it always has,
+                // so seeds keep giving the same games
+                if self.drawn == 0 {
+                    Shape::random(&mut self.rng)
+                } else {
+                    self.draw_bag(|rng| {
+                        let mut bag = create_new_7_bag(rng).to_vec();
+                        bag.reverse();
+                        bag
+                    })
+                }
+            },
+            Randomizer::FourteenBag => self.draw_bag(|rng| {
+                let mut bag = [SHAPES, SHAPES].concat();
+                bag.shuffle(rng);
+                bag
+            }),
+            Randomizer::Random => Shape::random(&mut self.rng),
+            Randomizer::Nes => {
+                // An eighth roll stands for a reroll, as does repeating the last piece
+                let roll = self.rng.random_range(0..8);
+                match SHAPES.get(roll) {
+                    Some(shape) if self.history.last() != Some(shape) => *shape,
+                    _ => Shape::random(&mut self.rng),
+                }
+            },
+            Randomizer::Tgm => self.tgm(),
+            Randomizer::BagPlusOne => self.draw_bag(|rng| {
+                let mut bag = SHAPES.to_vec();
+                bag.push(Shape::random(rng));
+                bag.shuffle(rng);
+                bag
+            }),
+        };
+
+        // Only the last few are ever looked back on
+        if self.history.len() == 4 {
+            self.history.remove(0);
+        }
+        self.history.push(shape);
+        self.drawn += 1;
+        shape
+    }
+
+    /// Takes the next piece from the bag, filling it first if it is empty
+    fn draw_bag(&mut self, fill: impl FnOnce(&mut StdRng) -> Vec<Shape>) -> Shape {
+        if self.bag.is_empty() {
+            self.bag = fill(&mut self.rng);
+        }
+        self.bag.pop().unwrap()
+    }
+
+    /// Rolls for a piece that isn't in the history, taking the last reroll if none is found
+    fn tgm(&mut self) -> Shape {
+        // The first piece is never S, Z or O
+        if self.drawn == 0 {
+            return TGM_FIRST[self.rng.random_range(0..TGM_FIRST.len())];
+        }
+
+        let mut shape = Shape::random(&mut self.rng);
+        for _ in 0..TGM_REROLLS {
+            if !self.history.contains(&shape) {
+                break;
+            }
+            shape = Shape::random(&mut self.rng);
+        }
+        shape
+    }
+}
+
+/// Creates a new 7 bag array
+pub fn create_new_7_bag(rng: &mut impl Rng) -> [Shape;7]{
+    let mut new_queue: [Option<Shape>;7] = [None;7];
+
+    // Assign each shape
+    for x in 0..7 {
+        // Loop until available shape found
+        'new_shape: loop {
+            // Get new shape
+            let new_shape = Shape::random(rng);
+
+            // Check if shape exists in queue
+            for shape in new_queue.into_iter().flatten() {
+                if shape == new_shape { // Shape already in queue
+                    continue 'new_shape;
+                }
+            }
+
+            // Shape not found in queue
+            new_queue[x] = Some(new_shape);
+            break 'new_shape;
+        }
+    }
+
+    // Return queue
+    new_queue.map(|shape| shape.unwrap())
+}
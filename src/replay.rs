@@ -0,0 +1,441 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::{
+    game_state::{Button, GamePhase, GameState, Input},
+    randomizer::Randomizer,
+    rotation_system::RotationSystem,
+    rules::{AttackTable, BoardSize, GameMode, Gravity, Handling, LockMode, Ruleset},
+    storage,
+};
+
+/// First bytes of every replay file
+const MAGIC: &[u8; 4] = b"JTRP";
+
+/// Format version written by this build
+///
+/// Version 2 added received garbage and the garbage rules, version 3 the
+/// board size, version 4 the randomizer and version 5 the rotation system.
+/// Version 6 stores the soft drop factor one up, so a factor of 0 isn't
+/// mistaken for instant. Version 7 gave the TGM randomizer its sixth reroll,
+/// so earlier TGM games can't be played back.
+pub const VERSION: u8 = 7;
+
+/// Every input of a game with what is needed to play it back exactly
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub rules: Ruleset,
+    /// Inputs in the order they were applied, with the tick they were applied on
+    pub inputs: Vec<(u64, Input)>,
+    /// Tick the game ended on
+    pub length: u64,
+}
+
+impl Replay {
+    /// Captures a game played so far
+    pub fn from_game(game: &GameState) -> Self {
+        Replay {
+            seed: game.seed,
+            rules: game.rules.clone(),
+            inputs: game.input_log.clone(),
+            length: game.tick,
+        }
+    }
+
+    /// Default directory replays are saved in
+    pub fn default_dir() -> Option<PathBuf> {
+        storage::data_dir().map(|dir| dir.join("replays"))
+    }
+
+    /// Loads a replay file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Replay::decode(&fs::read(path)?)
+    }
+
+    /// Saves a replay file, creating the directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.encode())
+    }
+
+    /// Encodes the replay into its compact binary form
+    ///
+    /// Ticks are stored as the gap since the previous input, so a whole game
+    /// usually takes two bytes a button press.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        put_rules(&mut bytes, &self.rules);
+        put_varint(&mut bytes, self.length);
+
+        // Inputs
+        put_varint(&mut bytes, self.inputs.len() as u64);
+        let mut last_tick = 0;
+        for (tick, input) in &self.inputs {
+            put_varint(&mut bytes, tick - last_tick);
+            put_input(&mut bytes, *input);
+            last_tick = *tick;
+        }
+
+        bytes
+    }
+
+    /// Decodes a replay from its binary form
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader::new(bytes);
+
+        // Header
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not a jordtris replay"));
+        }
+        let version = reader.byte()?;
+        if version == 0 || version > VERSION {
+            return Err(invalid(&format!("unsupported replay version {version}")));
+        }
+        reader.version = version;
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let rules = reader.rules()?;
+        let length = reader.varint()?;
+
+        // Inputs
+        let count = reader.varint()?;
+        let mut inputs = vec![];
+        let mut tick = 0;
+        for _ in 0..count {
+            tick += reader.varint()?;
+            inputs.push((tick, reader.input()?));
+        }
+
+        Ok(Replay { seed, rules, inputs, length })
+    }
+}
+
+/// Plays a replay back through the engine one tick at a time
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub game: GameState,
+    /// Index of the next input to apply
+    next_input: usize,
+}
+
+impl ReplayPlayer {
+    /// Starts playing a replay from the beginning
+    pub fn new(replay: Replay) -> Self {
+        let game = GameState::with_rules(replay.seed, replay.rules.clone());
+        ReplayPlayer { replay, game, next_input: 0 }
+    }
+
+    /// Determines if the game has reached the end of the replay
+    pub fn is_finished(&self) -> bool {
+        self.game.tick >= self.replay.length || self.game.game_phase != GamePhase::Playing
+    }
+
+    /// Advances the game by one tick with the inputs recorded for it
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        // Gather this tick's inputs
+        let start = self.next_input;
+        while self.replay.inputs.get(self.next_input).is_some_and(|(tick, _)| *tick == self.game.tick) {
+            self.next_input += 1;
+        }
+        let inputs: Vec<Input> = self.replay.inputs[start..self.next_input].iter()
+            .map(|(_, input)| *input)
+            .collect();
+
+        self.game.step(&inputs);
+    }
+
+    /// Moves playback to a tick, replaying from the start if it is behind
+    pub fn seek(&mut self, tick: u64) {
+        if tick < self.game.tick {
+            *self = ReplayPlayer::new(self.replay.clone());
+        }
+        while self.game.tick < tick && !self.is_finished() {
+            self.step();
+        }
+    }
+}
+
+/// Creates an error for a malformed replay
+pub(crate) fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Marks a release in an input byte
+const RELEASE: u8 = 0x80;
+
+/// Input byte for received garbage, followed by the number of lines
+const GARBAGE: u8 = 0x40;
+
+/// Appends an input, buttons are their index with the high bit marking a release
+pub(crate) fn put_input(bytes: &mut Vec<u8>, input: Input) {
+    let (button, release) = match input {
+        Input::Press(button) => (button, 0),
+        Input::Release(button) => (button, RELEASE),
+        Input::Garbage(lines) => {
+            bytes.push(GARBAGE);
+            put_varint(bytes, lines as u64);
+            return;
+        },
+    };
+    let idx = BUTTONS.iter().position(|b| *b == button).unwrap() as u8;
+    bytes.push(idx | release);
+}
+
+/// Buttons in the order they are numbered in replay files, only ever append
+const BUTTONS: [Button; 7] = [
+    Button::Left,
+    Button::Right,
+    Button::RotateCw,
+    Button::RotateCcw,
+    Button::SoftDrop,
+    Button::HardDrop,
+    Button::Hold,
+];
+
+/// Appends a number as a LEB128 varint
+pub(crate) fn put_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Appends the rules a replay was played with
+fn put_rules(bytes: &mut Vec<u8>, rules: &Ruleset) {
+    // Mode
+    match rules.mode {
+        GameMode::Endless => bytes.push(0),
+        GameMode::Sprint { lines } => {
+            bytes.push(1);
+            put_varint(bytes, lines as u64);
+        },
+        GameMode::Ultra { seconds } => {
+            bytes.push(2);
+            put_varint(bytes, seconds as u64);
+        },
+        GameMode::Dig { lines, messiness } => {
+            bytes.push(3);
+            put_varint(bytes, lines as u64);
+            put_varint(bytes, messiness as u64);
+        },
+    }
+
+    put_varint(bytes, rules.start_level as u64);
+    put_varint(bytes, rules.lines_per_level as u64);
+
+    // Gravity
+    match &rules.gravity {
+        Gravity::Guideline => bytes.push(0),
+        Gravity::Fixed(gravity) => {
+            bytes.push(1);
+            put_varint(bytes, *gravity as u64);
+        },
+        Gravity::Table(table) => {
+            bytes.push(2);
+            put_varint(bytes, table.len() as u64);
+            for gravity in table {
+                put_varint(bytes, *gravity as u64);
+            }
+        },
+    }
+
+    // Locking
+    match rules.lock_mode {
+        LockMode::Extended { max_resets } => {
+            bytes.push(0);
+            put_varint(bytes, max_resets as u64);
+        },
+        LockMode::Infinite => bytes.push(1),
+        LockMode::StepReset => bytes.push(2),
+        LockMode::Classic => bytes.push(3),
+    }
+    put_varint(bytes, rules.lock_delay as u64);
+
+    // Handling, soft drop factor 0 is instant and the rest are one up
+    put_varint(bytes, rules.handling.das as u64);
+    put_varint(bytes, rules.handling.arr as u64);
+    put_varint(bytes, rules.handling.soft_drop_factor.map_or(0, |factor| factor as u64 + 1));
+
+    // Garbage
+    match rules.attack {
+        AttackTable::Guideline => bytes.push(0),
+        AttackTable::Classic => bytes.push(1),
+    }
+    put_varint(bytes, rules.garbage_delay as u64);
+
+    // Board
+    put_varint(bytes, rules.board.width as u64);
+    put_varint(bytes, rules.board.height as u64);
+    put_varint(bytes, rules.board.hidden_rows as u64);
+
+    // Pieces
+    bytes.push(Randomizer::ALL.iter().position(|randomizer| *randomizer == rules.randomizer).unwrap() as u8);
+    bytes.push(RotationSystem::ALL.iter().position(|system| *system == rules.rotation_system).unwrap() as u8);
+}
+
+/// Reads through the bytes of a replay
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Format version of the replay being read
+    version: u8,
+}
+
+impl<'a> Reader<'a> {
+    /// Starts reading from the beginning, in the current format version
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0, version: VERSION }
+    }
+
+    /// Determines if every byte has been read
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    /// Reads a number of raw bytes
+    pub(crate) fn take(&mut self, len: usize) -> io::Result<&[u8]> {
+        let end = self.pos + len;
+        let bytes = self.bytes.get(self.pos..end).ok_or_else(|| invalid("replay is truncated"))?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Reads a single byte
+    pub(crate) fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a LEB128 varint
+    pub(crate) fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint is too long"))
+    }
+
+    /// Reads a varint that has to fit in 32 bits
+    pub(crate) fn varint32(&mut self) -> io::Result<u32> {
+        u32::try_from(self.varint()?).map_err(|_| invalid("value out of range"))
+    }
+
+    /// Reads an input
+    pub(crate) fn input(&mut self) -> io::Result<Input> {
+        let byte = self.byte()?;
+        if byte == GARBAGE {
+            return Ok(Input::Garbage(self.varint32()?));
+        }
+
+        let button = *BUTTONS.get((byte & !RELEASE) as usize).ok_or_else(|| invalid("unknown input"))?;
+        if byte & RELEASE == 0 {
+            Ok(Input::Press(button))
+        } else {
+            Ok(Input::Release(button))
+        }
+    }
+
+    /// Reads the rules a replay was played with
+    fn rules(&mut self) -> io::Result<Ruleset> {
+        let mode = match self.byte()? {
+            0 => GameMode::Endless,
+            1 => GameMode::Sprint { lines: self.varint32()? },
+            2 => GameMode::Ultra { seconds: self.varint32()? },
+            3 => GameMode::Dig { lines: self.varint32()?, messiness: self.varint32()? },
+            _ => return Err(invalid("unknown game mode")),
+        };
+
+        let start_level = self.varint32()?;
+        let lines_per_level = self.varint32()?;
+
+        let gravity = match self.byte()? {
+            0 => Gravity::Guideline,
+            1 => Gravity::Fixed(self.varint32()?),
+            2 => {
+                let len = self.varint()?;
+                let mut table = vec![];
+                for _ in 0..len {
+                    table.push(self.varint32()?);
+                }
+                Gravity::Table(table)
+            },
+            _ => return Err(invalid("unknown gravity")),
+        };
+
+        let lock_mode = match self.byte()? {
+            0 => LockMode::Extended { max_resets: self.varint32()? },
+            1 => LockMode::Infinite,
+            2 => LockMode::StepReset,
+            3 => LockMode::Classic,
+            _ => return Err(invalid("unknown lock mode")),
+        };
+        let lock_delay = self.varint32()?;
+
+        let das = self.varint32()?;
+        let arr = self.varint32()?;
+        let soft_drop_factor = match self.varint()? {
+            0 => None,
+            factor => {
+                // Earlier versions stored factors as they are
+                let factor = if self.version < 6 { factor } else { factor - 1 };
+                Some(u32::try_from(factor).map_err(|_| invalid("value out of range"))?)
+            },
+        };
+
+        // Version 1 replays had no garbage
+        let mut rules = Ruleset {
+            mode,
+            start_level,
+            lines_per_level,
+            gravity,
+            lock_mode,
+            lock_delay,
+            handling: Handling { das, arr, soft_drop_factor },
+            ..Ruleset::default()
+        };
+        if self.version >= 2 {
+            rules.attack = match self.byte()? {
+                0 => AttackTable::Guideline,
+                1 => AttackTable::Classic,
+                _ => return Err(invalid("unknown attack table")),
+            };
+            rules.garbage_delay = self.varint32()?;
+        }
+        if self.version >= 3 {
+            rules.board = BoardSize {
+                width: self.varint32()? as usize,
+                height: self.varint32()? as usize,
+                hidden_rows: self.varint32()? as usize,
+            };
+            if !rules.board.is_valid() {
+                return Err(invalid("invalid board size"));
+            }
+        }
+        if self.version >= 4 {
+            rules.randomizer = *Randomizer::ALL.get(self.byte()? as usize).ok_or_else(|| invalid("unknown randomizer"))?;
+            if rules.randomizer == Randomizer::Tgm && self.version < 7 {
+                return Err(invalid("replay uses an older tgm randomizer"));
+            }
+        }
+        if self.version >= 5 {
+            rules.rotation_system = *RotationSystem::ALL.get(self.byte()? as usize).ok_or_else(|| invalid("unknown rotation system"))?;
+        }
+
+        Ok(rules)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    board::Board,
+    game_state::Coord,
+    shapes::{Rotation, Shape},
+};
+
+/// How pieces sit in their box and get kicked when rotating
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RotationSystem {
+    /// The guideline Super Rotation System
+    Srs,
+
+    /// SRS with the I piece kicking the same either way round, like TETR.IO
+    SrsPlus,
+
+    /// Arika's system from TGM, pieces spawn flat side up and kick a column
+    /// right or left unless stopped by the centre column rule
+    Ars,
+
+    /// Nintendo's system from the NES game, right handed and without kicks
+    Nrs,
+
+    /// SRS pieces that only rotate where they are
+    NoKicks,
+}
+
+impl RotationSystem {
+    /// Every rotation system, in the order replays number them
+    pub const ALL: [RotationSystem; 5] = [
+        RotationSystem::Srs,
+        RotationSystem::SrsPlus,
+        RotationSystem::Ars,
+        RotationSystem::Nrs,
+        RotationSystem::NoKicks,
+    ];
+
+    /// Short name of the rotation system, as given on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            RotationSystem::Srs => "srs",
+            RotationSystem::SrsPlus => "srs+",
+            RotationSystem::Ars => "ars",
+            RotationSystem::Nrs => "nrs",
+            RotationSystem::NoKicks => "none",
+        }
+    }
+
+    /// Parses a rotation system from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        RotationSystem::ALL.into_iter().find(|system| system.name() == name)
+    }
+
+    /// Gets the cells of a piece's box in a rotation
+    pub fn cells(&self, shape: Shape, rotation: Rotation) -> [[bool; 4]; 4] {
+        use Rotation::*;
+        use Shape::*;
+
+        // ARS and NRS states are SRS ones, T, J and L spawning pointing down
+        let flipped = rotation.rotate_cw().rotate_cw();
+        let vertical = matches!(rotation, R90 | R270);
+        let state = match (self, shape) {
+            (RotationSystem::Srs | RotationSystem::SrsPlus | RotationSystem::NoKicks, _) | (_, O) => rotation,
+            (_, J | L | T) => flipped,
+            (RotationSystem::Ars, I) => if vertical { R90 } else { R0 },
+            (RotationSystem::Ars, S) => if vertical { R270 } else { R180 },
+            (RotationSystem::Ars, Z) => if vertical { R90 } else { R180 },
+            (_, I | S) => if vertical { R90 } else { R180 },
+            (_, Z) => if vertical { R270 } else { R180 },
+        };
+
+        // ARS keeps pieces pointing up at the bottom of their box
+        let mut cells = shape.get_shape(&state);
+        if *self == RotationSystem::Ars && matches!(shape, J | L | T) && rotation == R180 {
+            cells.rotate_right(1);
+        }
+        cells
+    }
+
+    /// Gets where the top left of a piece's box spawns on the standard board
+    ///
+    /// Every piece spawns with its top row just below the hidden rows, and
+    /// left of centre if it is 3 wide.
+    pub fn spawn_offset(&self, shape: Shape) -> Coord {
+        let cells = self.cells(shape, Rotation::R0);
+        let top = cells.iter().position(|row| row.contains(&true)).unwrap_or(0);
+        Coord { x: 3, y: 2 - top as i16 }
+    }
+
+    /// Gets the offsets tried in order when rotating, with y up
+    pub fn kicks(&self, shape: Shape, from: Rotation, to: Rotation) -> &'static [(i16, i16)] {
+        match (self, shape) {
+            (RotationSystem::Srs, _) => srs_kicks(shape, from, to),
+            (RotationSystem::SrsPlus, Shape::I) => srs_plus_i_kicks(from, to),
+            (RotationSystem::SrsPlus, _) => srs_kicks(shape, from, to),
+            (RotationSystem::Ars, Shape::J | Shape::L | Shape::S | Shape::T | Shape::Z) => &[(0, 0), (1, 0), (-1, 0)],
+            _ => &[(0, 0)],
+        }
+    }
+
+    /// Rotates a piece on a board, giving where it ends up and the index of the kick used
+    ///
+    /// None if every kick is blocked.
+    pub fn rotate(&self, board: &Board, shape: Shape, from: Rotation, to: Rotation, pos: &Coord) -> Option<(Coord, usize)> {
+        let cells = self.cells(shape, to);
+        let mut kicks = self.kicks(shape, from, to);
+
+        // ARS won't kick T, J or L out of a flat state if the first cell in the
+        // way, reading along the rows, is in the centre column
+        if *self == RotationSystem::Ars && matches!(shape, Shape::J | Shape::L | Shape::T) && matches!(from, Rotation::R0 | Rotation::R180) {
+            let blocked = (0..4)
+                .flat_map(|dy| (0..4).map(move |dx| (dx, dy)))
+                .filter(|(dx, dy)| cells[*dy][*dx])
+                .find(|(dx, dy)| board.is_filled(pos.x + *dx as i16, pos.y + *dy as i16));
+            if blocked.is_some_and(|(dx, _)| dx == 1) {
+                kicks = &kicks[..1];
+            }
+        }
+
+        kicks.iter().enumerate().find_map(|(kick, (dx, dy))| {
+            // Kick data is y up but the board is y down
+            let to = Coord { x: pos.x + dx, y: pos.y - dy };
+            board.fits(&cells, &to).then_some((to, kick))
+        })
+    }
+}
+
+/// Gets the SRS wall kick data for a shape
+fn srs_kicks(shape: Shape, from: Rotation, to: Rotation) -> &'static [(i16, i16)] {
+    use Rotation::*;
+    use Shape::*;
+
+    match shape {
+        J | L | S | T | Z => {
+            match (from, to) {
+                // 0 - R
+                (R0, R90) => &[(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)],
+                (R90, R0) => &[(0,0), (1,0), (1,-1), (0,2), (1,2)],
+
+                // R - 2
+                (R90, R180) => &[(0,0), (1,0), (1,-1), (0,2), (1,2)],
+                (R180, R90) => &[(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)],
+
+                // 2 - L
+                (R180, R270) => &[(0,0), (1,0), (1,1), (0,-2), (1,-2)],
+                (R270, R180) => &[(0,0), (-1,0), (-1,-1), (0,2), (-1,2)],
+
+                // L - 0
+                (R270, R0) => &[(0,0), (-1,0), (-1,-1), (0,2), (-1,2)],
+                (R0, R270) => &[(0,0), (1,0), (1,1), (0,-2), (1,-2)],
+
+                _ => unreachable!()
+            }
+        },
+        O => &[(0,0)], // i love you so much O piece please be my wife
+        I => match (from, to) {
+            // 0 - R
+            (R0, R90) => &[(0,0), (-2,0), (1,0), (-2,-1), (1,2)],
+            (R90, R0) => &[(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
+
+            // R - 2
+            (R90, R180) => &[(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
+            (R180, R90) => &[(0,0), (1,0), (-2,1), (1,-2), (-2,1)],
+
+            // 2 - L
+            (R180, R270) => &[(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
+            (R270, R180) => &[(0,0), (-2,0), (1,0), (-2,-1), (1,2)],
+
+            // L - 0
+            (R270, R0) => &[(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
+            (R0, R270) => &[(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
+
+            _ => unreachable!()
+        },
+    }
+}
+
+/// Gets the SRS+ kicks for the I piece, mirrored so neither side is favoured
+fn srs_plus_i_kicks(from: Rotation, to: Rotation) -> &'static [(i16, i16)] {
+    use Rotation::*;
+
+    match (from, to) {
+        // 0 - R
+        (R0, R90) => &[(0,0), (1,0), (-2,0), (-2,-1), (1,2)],
+        (R90, R0) => &[(0,0), (-1,0), (2,0), (-1,-2), (2,1)],
+
+        // R - 2
+        (R90, R180) => &[(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
+        (R180, R90) => &[(0,0), (-2,0), (1,0), (-2,1), (1,-2)],
+
+        // 2 - L
+        (R180, R270) => &[(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
+        (R270, R180) => &[(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
+
+        // L - 0
+        (R270, R0) => &[(0,0), (1,0), (-2,0), (1,2), (-2,-1)],
+        (R0, R270) => &[(0,0), (-1,0), (2,0), (2,-1), (-1,2)],
+
+        _ => unreachable!()
+    }
+}
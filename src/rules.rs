@@ -0,0 +1,320 @@
+use crate::{game_state::{Coord, TICKS_PER_SECOND}, randomizer::Randomizer, rotation_system::RotationSystem, scoring::{ClearScore, TSpin}, shapes::Shape};
+
+/// Gravity of one row per tick, gravity values are in fractions of this
+pub const ONE_G: u32 = 1 << 16;
+
+/// Strongest gravity, pieces drop straight to the floor
+pub const MAX_GRAVITY: u32 = 20 * ONE_G;
+
+/// Guideline gravity `(0.8 - (level - 1) * 0.007) ^ (level - 1)` seconds per row
+/// at 60 ticks a second, precomputed so every platform falls identically
+const GUIDELINE_GRAVITY: [u32; 19] = [
+    1092, 1377, 1768, 2311, 3075, 4169, 5759, 8107, 11634, 17026,
+    25416, 38709, 60169, 95483, 154742, 256187, 433425, 749597, MAX_GRAVITY,
+];
+
+/// How fast pieces fall at each level
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Gravity {
+    /// The guideline curve, reaching 20G at level 19
+    Guideline,
+
+    /// The same gravity at every level
+    Fixed(u32),
+
+    /// Gravity per level starting at level 1, the last entry repeats
+    Table(Vec<u32>),
+}
+
+impl Gravity {
+    /// Gets the gravity for a level, in units of `ONE_G`
+    pub fn for_level(&self, level: u32) -> u32 {
+        let idx = level.saturating_sub(1) as usize;
+        let gravity = match self {
+            Gravity::Guideline => GUIDELINE_GRAVITY[idx.min(GUIDELINE_GRAVITY.len() - 1)],
+            Gravity::Fixed(gravity) => *gravity,
+            Gravity::Table(table) => match table.get(idx).or(table.last()) {
+                Some(gravity) => *gravity,
+                None => GUIDELINE_GRAVITY[0],
+            },
+        };
+
+        gravity.min(MAX_GRAVITY)
+    }
+}
+
+/// Garbage sent for each combo length, the last entry repeats
+const GUIDELINE_COMBO_ATTACK: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+/// How many garbage lines a clear sends to the opponent
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AttackTable {
+    /// Guideline attack with T-spin, back to back, combo and perfect clear bonuses
+    Guideline,
+
+    /// Only doubles, triples and tetrises send, 1, 2 and 4 lines
+    Classic,
+}
+
+impl AttackTable {
+    /// Gets the lines of garbage a clear sends
+    pub fn attack(&self, clear: &ClearScore) -> u32 {
+        let lines = match clear.lines {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            _ => 4,
+        };
+        if *self == AttackTable::Classic {
+            return lines;
+        }
+
+        let base = match clear.tspin {
+            TSpin::None => lines,
+            TSpin::Mini => clear.lines.saturating_sub(1),
+            TSpin::Full => 2 * clear.lines,
+        };
+        let back_to_back = if clear.back_to_back { 1 } else { 0 };
+        let combo = GUIDELINE_COMBO_ATTACK[(clear.combo as usize).min(GUIDELINE_COMBO_ATTACK.len() - 1)];
+        let perfect_clear = if clear.perfect_clear { 10 } else { 0 };
+
+        base + back_to_back + combo + perfect_clear
+    }
+}
+
+/// When a grounded piece's lock timer starts over
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LockMode {
+    /// Guideline extended placement, moving or rotating resets the timer a
+    /// limited number of times, and reaching a new lowest row restores them
+    Extended { max_resets: u32 },
+
+    /// Every successful move or rotation resets the timer
+    Infinite,
+
+    /// Only moving down a row resets the timer
+    StepReset,
+
+    /// The timer starts on first touching down and never resets
+    Classic,
+}
+
+impl LockMode {
+    /// Guideline extended placement with 15 resets
+    pub const GUIDELINE: LockMode = LockMode::Extended { max_resets: 15 };
+
+    /// Parses a lock mode from its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "extended" => Some(LockMode::GUIDELINE),
+            "infinite" => Some(LockMode::Infinite),
+            "step" => Some(LockMode::StepReset),
+            "classic" => Some(LockMode::Classic),
+            _ => None,
+        }
+    }
+}
+
+/// How the engine turns held buttons into movement, all times in ticks
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Handling {
+    /// Delayed auto shift, how long a direction is held before it repeats
+    pub das: u32,
+    /// Auto repeat rate, ticks between repeats once charged, 0 is instant
+    pub arr: u32,
+    /// Soft drop gravity as a multiple of normal gravity, none is instant
+    pub soft_drop_factor: Option<u32>,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: 10,
+            arr: 2,
+            soft_drop_factor: Some(20),
+        }
+    }
+}
+
+/// Dimensions of the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BoardSize {
+    pub width: usize,
+    /// Rows including the hidden ones
+    pub height: usize,
+    /// Rows at the top above the visible area, pieces spawn just below them
+    pub hidden_rows: usize,
+}
+
+impl BoardSize {
+    /// Narrowest board, just wide enough for an I piece lying down
+    pub const MIN_WIDTH: usize = 4;
+
+    /// Widest board the bitboard has room for
+    pub const MAX_WIDTH: usize = 26;
+
+    /// Fewest visible rows, enough for any piece standing up
+    pub const MIN_VISIBLE_ROWS: usize = 4;
+
+    /// Most rows in total
+    pub const MAX_HEIGHT: usize = 100;
+
+    /// Determines if a game can be played on a board this size
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN_WIDTH..=Self::MAX_WIDTH).contains(&self.width)
+            && self.height <= Self::MAX_HEIGHT
+            && self.height >= self.hidden_rows + Self::MIN_VISIBLE_ROWS
+    }
+
+    /// Gets the rows shown to the player
+    pub fn visible_rows(&self) -> usize {
+        self.height - self.hidden_rows
+    }
+
+    /// Moves where a piece's box spawns on the standard board to this one
+    ///
+    /// Pieces stay centred in the top visible rows.
+    pub fn spawn(&self, offsets: Coord) -> Coord {
+        let standard = BoardSize::default();
+        Coord {
+            x: offsets.x + (self.width as i16 - standard.width as i16) / 2,
+            y: offsets.y + self.hidden_rows as i16 - standard.hidden_rows as i16,
+        }
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        BoardSize {
+            width: 10,
+            height: 22,
+            hidden_rows: 2,
+        }
+    }
+}
+
+/// What the player is trying to do, and when the game ends
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    /// Play until topping out
+    Endless,
+
+    /// Clear a number of lines as fast as possible
+    Sprint { lines: u32 },
+
+    /// Score as many points as possible before time runs out
+    Ultra { seconds: u32 },
+
+    /// Dig through a number of garbage lines as fast as possible
+    ///
+    /// Messiness is the percent chance each garbage row's hole moves from
+    /// the one below it.
+    Dig { lines: u32, messiness: u32 },
+}
+
+impl GameMode {
+    /// The standard 40 line sprint
+    pub const SPRINT: GameMode = GameMode::Sprint { lines: 40 };
+
+    /// The standard 2 minute ultra
+    pub const ULTRA: GameMode = GameMode::Ultra { seconds: 120 };
+
+    /// A 100 line cheese race
+    pub const DIG: GameMode = GameMode::Dig { lines: 100, messiness: 100 };
+
+    /// Display name of the mode, also used as its high score category
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Endless => "Endless".to_string(),
+            GameMode::Sprint { lines } => format!("Sprint {lines}L"),
+            GameMode::Ultra { seconds } => format!("Ultra {}:{:02}", seconds / 60, seconds % 60),
+            GameMode::Dig { lines, messiness: 100 } => format!("Dig {lines}L"),
+            GameMode::Dig { lines, messiness } => format!("Dig {lines}L {messiness}%"),
+        }
+    }
+
+    /// Parses a mode from its display name
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name == "Endless" {
+            return Some(GameMode::Endless);
+        }
+
+        if let Some(time) = name.strip_prefix("Ultra ") {
+            let (minutes, seconds) = time.split_once(':')?;
+            let seconds = minutes.parse::<u32>().ok()? * 60 + seconds.parse::<u32>().ok()?;
+            return Some(GameMode::Ultra { seconds });
+        }
+
+        if let Some(dig) = name.strip_prefix("Dig ") {
+            let (lines, messiness) = match dig.split_once(' ') {
+                Some((lines, messiness)) => (lines, messiness.strip_suffix('%')?.parse().ok()?),
+                None => (dig, 100),
+            };
+            let lines = lines.strip_suffix('L')?.parse().ok()?;
+            return Some(GameMode::Dig { lines, messiness });
+        }
+
+        let lines = name.strip_prefix("Sprint ")?.strip_suffix('L')?;
+        Some(GameMode::Sprint { lines: lines.parse().ok()? })
+    }
+
+    /// Gets the number of ticks the mode lasts for, if it is timed
+    pub fn time_limit(&self) -> Option<u64> {
+        match self {
+            GameMode::Ultra { seconds } => Some(*seconds as u64 * TICKS_PER_SECOND as u64),
+            _ => None,
+        }
+    }
+
+    /// Determines if games of the mode are ranked by time rather than score
+    pub fn ranks_by_time(&self) -> bool {
+        matches!(self, GameMode::Sprint { .. } | GameMode::Dig { .. })
+    }
+}
+
+/// Rules a game is played with, fixed for the whole game
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Ruleset {
+    pub mode: GameMode,
+    pub start_level: u32,
+    /// Lines to clear before the level goes up
+    pub lines_per_level: u32,
+    pub gravity: Gravity,
+    pub lock_mode: LockMode,
+    /// Ticks a grounded piece waits before locking
+    pub lock_delay: u32,
+    /// Part of the rules so replays reproduce movement exactly
+    pub handling: Handling,
+    pub attack: AttackTable,
+    /// Ticks received garbage waits before it can rise
+    pub garbage_delay: u32,
+    pub board: BoardSize,
+    pub randomizer: Randomizer,
+    pub rotation_system: RotationSystem,
+}
+
+impl Ruleset {
+    /// Gets where the top left of a piece's box spawns
+    pub fn spawn(&self, shape: &Shape) -> Coord {
+        self.board.spawn(self.rotation_system.spawn_offset(*shape))
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            mode: GameMode::Endless,
+            start_level: 1,
+            lines_per_level: 10,
+            gravity: Gravity::Guideline,
+            lock_mode: LockMode::GUIDELINE,
+            lock_delay: 30,
+            handling: Handling::default(),
+            attack: AttackTable::Guideline,
+            garbage_delay: 20,
+            board: BoardSize::default(),
+            randomizer: Randomizer::SevenBag,
+            rotation_system: RotationSystem::Srs,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+/// Points awarded per cell of soft drop
+pub const SOFT_DROP_POINTS: u32 = 1;
+
+/// Points awarded per cell of hard drop
+pub const HARD_DROP_POINTS: u32 = 2;
+
+/// How the last locked T piece was spun into place, ordered from none to full
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+/// Something notable the engine reports to the front end
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    /// The player dropped the piece some number of cells
+    Drop { hard: bool, cells: u32, points: u32 },
+
+    /// A locked piece cleared lines or was a T-spin
+    Clear(ClearScore),
+
+    /// Lines of garbage to send to opponents, left over after cancelling incoming garbage
+    Attack(u32),
+}
+
+/// Breakdown of the points awarded for a single line clear
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClearScore {
+    pub lines: u32,
+    pub tspin: TSpin,
+    pub level: u32,
+    /// Number of consecutive clears before this one, 0 on the first clear
+    pub combo: u32,
+    pub back_to_back: bool,
+    pub perfect_clear: bool,
+    /// Points for the clear itself, scaled by level
+    pub base: u32,
+    pub back_to_back_bonus: u32,
+    pub combo_bonus: u32,
+    pub perfect_clear_bonus: u32,
+}
+
+impl ClearScore {
+    /// Scores a clear given the current level, combo and back to back state
+    pub fn new(lines: u32, tspin: TSpin, level: u32, combo: u32, back_to_back: bool, perfect_clear: bool) -> Self {
+        let base = clear_points(lines, tspin) * level;
+
+        // Difficult clears in a row are worth 50% more
+        let back_to_back = back_to_back && is_difficult(lines, tspin);
+        let back_to_back_bonus = if back_to_back { base / 2 } else { 0 };
+
+        // Perfect clears award a flat bonus on top
+        let perfect_clear_bonus = if perfect_clear {
+            perfect_clear_points(lines, back_to_back) * level
+        } else {
+            0
+        };
+
+        ClearScore {
+            lines,
+            tspin,
+            level,
+            combo,
+            back_to_back,
+            perfect_clear,
+            base,
+            back_to_back_bonus,
+            combo_bonus: 50 * combo * level,
+            perfect_clear_bonus,
+        }
+    }
+
+    /// Total points awarded for the clear
+    pub fn total(&self) -> u32 {
+        self.base + self.back_to_back_bonus + self.combo_bonus + self.perfect_clear_bonus
+    }
+
+    /// Short name of the clear for display
+    pub fn label(&self) -> String {
+        let lines = match self.lines {
+            0 => "",
+            1 => "SINGLE",
+            2 => "DOUBLE",
+            3 => "TRIPLE",
+            _ => "TETRIS",
+        };
+        let name = match self.tspin {
+            TSpin::None => lines.to_string(),
+            TSpin::Mini => format!("T-SPIN MINI {lines}").trim_end().to_string(),
+            TSpin::Full => format!("T-SPIN {lines}").trim_end().to_string(),
+        };
+
+        if self.perfect_clear {
+            "PERFECT CLEAR".to_string()
+        } else if self.back_to_back {
+            format!("B2B {name}")
+        } else if self.combo > 0 {
+            format!("{name} {}x", self.combo)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// Base points for clearing lines at level 1
+pub fn clear_points(lines: u32, tspin: TSpin) -> u32 {
+    match tspin {
+        TSpin::None => match lines {
+            0 => 0,
+            1 => 100,
+            2 => 300,
+            3 => 500,
+            _ => 800,
+        },
+        TSpin::Mini => match lines {
+            0 => 100,
+            1 => 200,
+            _ => 400,
+        },
+        TSpin::Full => match lines {
+            0 => 400,
+            1 => 800,
+            2 => 1200,
+            _ => 1600,
+        },
+    }
+}
+
+/// Perfect clear bonus at level 1
+pub fn perfect_clear_points(lines: u32, back_to_back: bool) -> u32 {
+    match lines {
+        0 => 0,
+        1 => 800,
+        2 => 1200,
+        3 => 1800,
+        _ if back_to_back => 3200,
+        _ => 2000,
+    }
+}
+
+/// Determines if a clear keeps the back to back chain going
+pub fn is_difficult(lines: u32, tspin: TSpin) -> bool {
+    lines >= 4 || (lines > 0 && tspin != TSpin::None)
+}
@@ -1,7 +1,7 @@
-use crossterm::style::{StyledContent, Stylize};
-use crate::game_state::Coord;
+use rand::Rng;
 
 /// Rotation object
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Rotation {
     R0,
     R90,
@@ -43,7 +43,7 @@ impl Rotation {
 }
 
 /// All colors the various shapes can be
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ShapeColor {
     Cyan,
     Blue,
@@ -52,35 +52,35 @@ pub enum ShapeColor {
     Green,
     Purple,
     Red,
+    /// Rows of junk added to the board rather than placed by the player
+    Garbage,
     None,
 }
 
 impl ShapeColor {
     /// Determines if the color is representative of a block
     pub fn is_block(&self) -> bool {
-        match self {
-            ShapeColor::None => false,
-            _ => true,
-        }
+        !matches!(self, ShapeColor::None)
     }
 
-    /// Returns a tile styled based on the color
-    pub fn color_tile(&self) -> StyledContent<&str> {
+    /// Gets the letter of the piece that leaves this color, or `G` for garbage
+    pub fn letter(&self) -> Option<char> {
         match self {
-            ShapeColor::Cyan => "██".cyan(),
-            ShapeColor::Blue => "██".blue(),
-            ShapeColor::Orange => "██".dark_red(),
-            ShapeColor::Yellow => "██".yellow(),
-            ShapeColor::Green => "██".green(),
-            ShapeColor::Purple => "██".magenta(),
-            ShapeColor::Red => "██".red(),
-            _ => "██".reset()
+            ShapeColor::Cyan => Some('I'),
+            ShapeColor::Blue => Some('J'),
+            ShapeColor::Orange => Some('L'),
+            ShapeColor::Yellow => Some('O'),
+            ShapeColor::Green => Some('Z'),
+            ShapeColor::Purple => Some('T'),
+            ShapeColor::Red => Some('S'),
+            ShapeColor::Garbage => Some('G'),
+            ShapeColor::None => None,
         }
     }
 }
 
 // All possible shapes
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Shape {
     I,
     J,
@@ -106,6 +106,26 @@ impl Shape {
         }
     }
 
+    /// Gets the letter the shape is named by
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::I => "I",
+            Shape::J => "J",
+            Shape::L => "L",
+            Shape::O => "O",
+            Shape::Z => "Z",
+            Shape::T => "T",
+            Shape::S => "S",
+        }
+    }
+
+    /// Gets a shape by its letter
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Shape::I, Shape::J, Shape::L, Shape::O, Shape::Z, Shape::T, Shape::S]
+            .into_iter()
+            .find(|shape| shape.name() == name)
+    }
+
     /// Gets the next shape in order
     #[allow(unused)]
     pub fn get_next_shape_ord(&self) -> Self {
@@ -121,24 +141,10 @@ impl Shape {
         }
     }
 
-    /// Returns the spawn offsets (x, y) for each piece
-    pub fn get_spawn_offsets(&self) -> Coord {
-        match self {
-            Shape::I => Coord{x: 3, y: 1},
-            Shape::O => Coord{x: 3, y: 1},
-
-            // Other shapes are the same
-            Shape::J | Shape::L |
-            Shape::Z | Shape::T |
-            Shape::S => Coord{x: 3, y: 2},
-        }
-    }
-
-
-    /// Returns a random piece
-    pub fn random() -> Self {
+    /// Returns a random piece drawn from the given rng
+    pub fn random(rng: &mut impl Rng) -> Self {
         use Shape::*;
-        match rand::random_range(0..7) {
+        match rng.random_range(0..7) {
             0 => I,
             1 => J,
             2 => L,
@@ -150,57 +156,7 @@ impl Shape {
         }
     }
 
-    /// Gets the wall kick data for the current shape
-    pub fn get_kick_data(&self, from: &Rotation, to: &Rotation) -> [(i16, i16); 5] {
-        use Shape::*; 
-        use Rotation::*;
-
-        match self {
-            J | L | S | T | Z => {
-                match (from, to) {
-                    // 0 - R
-                    (R0, R90) => [(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)],
-                    (R90, R0) => [(0,0), (1,0), (1,-1), (0,2), (1,2)],
-
-                    // R - 2
-                    (R90, R180) => [(0,0), (1,0), (1,-1), (0,2), (1,2)],
-                    (R180, R90) => [(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)],
-
-                    // 2 - L
-                    (R180, R270) => [(0,0), (1,0), (1,1), (0,-2), (1,-2)],
-                    (R270, R180) => [(0,0), (-1,0), (-1,-1), (0,2), (-1,2)],
-
-                    // L - 0
-                    (R270, R0) => [(0,0), (-1,0), (-1,-1), (0,2), (-1,2)],
-                    (R0, R270) => [(0,0), (1,0), (1,1), (0,-2), (1,-2)],
-
-                    _ => unreachable!()
-                }
-            },
-            O => [(0,0); 5], // i love you so much O piece please be my wife
-            I => match(from, to) {
-                    // 0 - R
-                    (R0, R90) => [(0,0), (-2,0), (1,0), (-2,-1), (1,2)],
-                    (R90, R0) => [(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
-
-                    // R - 2
-                    (R90, R180) => [(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
-                    (R180, R90) => [(0,0), (1,0), (-2,1), (1,-2), (-2,1)],
-
-                    // 2 - L
-                    (R180, R270) => [(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
-                    (R270, R180) => [(0,0), (-2,0), (1,0), (-2,-1), (1,2)],
-
-                    // L - 0
-                    (R270, R0) => [(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
-                    (R0, R270) => [(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
-
-                    _ => unreachable!()
-            },
-        }
-    }
-
-    /// Gets the current shape array based on rotation
+    /// Gets the current shape array based on rotation, as SRS has it
     pub fn get_shape(&self, rot: &Rotation) -> [[bool; 4]; 4] {
         use Shape::*;
         use Rotation::*;
@@ -0,0 +1,143 @@
+use std::{io::{self, Write}, net::{Ipv4Addr, TcpListener, TcpStream}};
+
+use crate::{
+    game_state::{GamePhase, GameState},
+    json::Value,
+    shapes::Shape,
+};
+
+/// Bytes a spectator can fall behind by before it is dropped
+const MAX_BACKLOG: usize = 1 << 20;
+
+/// Streams the state of a game to anyone who connects, as one JSON object a line
+///
+/// Only listens on localhost. Spectators never send anything, and ones that
+/// can't keep up are disconnected rather than slowing the game down.
+pub struct SpectatorServer {
+    listener: TcpListener,
+    spectators: Vec<Spectator>,
+}
+
+/// A connected spectator
+struct Spectator {
+    stream: TcpStream,
+    /// Bytes waiting for room to be sent
+    outgoing: Vec<u8>,
+}
+
+impl SpectatorServer {
+    /// Starts listening for spectators on a local port
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(SpectatorServer { listener, spectators: vec![] })
+    }
+
+    /// Gets the number of spectators connected
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Accepts new spectators and sends the state of the game to every one
+    pub fn broadcast(&mut self, game: &GameState) {
+        self.accept();
+        if self.spectators.is_empty() {
+            return;
+        }
+
+        let line = format!("{}\n", snapshot(game));
+        self.spectators.retain_mut(|spectator| {
+            spectator.outgoing.extend_from_slice(line.as_bytes());
+            spectator.flush().is_ok() && spectator.outgoing.len() <= MAX_BACKLOG
+        });
+    }
+
+    /// Accepts every spectator waiting to connect
+    fn accept(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                let _ = stream.set_nodelay(true);
+                self.spectators.push(Spectator { stream, outgoing: vec![] });
+            }
+        }
+    }
+}
+
+impl Spectator {
+    /// Sends as much of the outgoing bytes as the connection will take
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(len) => {
+                    self.outgoing.drain(..len);
+                },
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Captures what a spectator needs to draw a game
+///
+/// Board rows are strings from the top, one character a cell: `.` for empty,
+/// the letter of the piece that left it, or `G` for garbage.
+pub fn snapshot(game: &GameState) -> Value {
+    let board: Vec<String> = game.board.iter()
+        .map(|row| row.iter().map(|color| color.letter().unwrap_or('.')).collect())
+        .collect();
+
+    // Cells of the falling piece in board coordinates
+    let shape = game.shape_cells(game.current_shape, game.rotation);
+    let mut cells = vec![];
+    for (y, row) in shape.iter().enumerate() {
+        for (x, filled) in row.iter().enumerate() {
+            if *filled {
+                let cell = vec![game.player_pos.x + x as i16, game.player_pos.y + y as i16];
+                cells.push(Value::from(cell));
+            }
+        }
+    }
+    let piece = Value::object([
+        ("shape", game.current_shape.name().into()),
+        ("rotation", game.rotation.get_string().into()),
+        ("x", game.player_pos.x.into()),
+        ("y", game.player_pos.y.into()),
+        ("ghost_y", game.get_drop_position(&shape).into()),
+        ("cells", Value::Array(cells)),
+    ]);
+
+    Value::object([
+        ("tick", game.tick.into()),
+        ("phase", phase_name(game.game_phase).into()),
+        ("mode", game.rules.mode.name().into()),
+        ("board", board.into()),
+        ("hidden_rows", (game.rules.board.hidden_rows as u32).into()),
+        ("piece", piece),
+        ("hold", game.held.map(|shape| shape.name()).into()),
+        ("hold_used", game.just_held.into()),
+        ("queue", game.shape_queue.iter().map(Shape::name).collect::<Vec<_>>().into()),
+        ("score", game.score.into()),
+        ("level", game.level.into()),
+        ("lines", game.lines.into()),
+        ("pieces", game.pieces.into()),
+        ("combo", game.combo.into()),
+        ("back_to_back", game.back_to_back.into()),
+        ("incoming_garbage", game.incoming_garbage().into()),
+        ("completed", game.completed.into()),
+    ])
+}
+
+/// Gets the name a game phase is sent as
+fn phase_name(phase: GamePhase) -> &'static str {
+    match phase {
+        GamePhase::Playing => "playing",
+        GamePhase::Paused => "paused",
+        GamePhase::GameOver => "game_over",
+        GamePhase::Help => "help",
+        GamePhase::Score => "score",
+    }
+}
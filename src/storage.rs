@@ -0,0 +1,101 @@
+use std::{env, fs, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::game_state::TICKS_PER_SECOND;
+
+/// Gets the directory Jordtris keeps its files in
+///
+/// Follows the XDG base directory spec, falling back to `~/.local/share`,
+/// or `%APPDATA%` on windows. Returns none if no home can be found.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                env::var_os("APPDATA").map(PathBuf::from)
+            } else {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+            }
+        })?;
+
+    Some(base.join("jordtris"))
+}
+
+/// Gets the path of a file in the data directory
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(name))
+}
+
+/// Reads the lines of a file written by `save_lines`, without the header or comments
+///
+/// A missing or empty file has no lines. A file starting with anything but
+/// the header is in another format or version, so is refused rather than
+/// misread.
+pub fn load_lines(path: &Path, header: &str) -> io::Result<Vec<String>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+
+    let mut lines = text.lines();
+    match lines.next() {
+        None => return Ok(vec![]),
+        Some(first) if first == header => {},
+        Some(_) => {
+            let msg = format!("{} doesn't start with \"{header}\"", path.display());
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        },
+    }
+    Ok(lines.filter(|line| !line.starts_with('#')).map(str::to_string).collect())
+}
+
+/// Writes a header then a line for each record, creating the directory if needed
+pub fn save_lines(path: &Path, header: &str, lines: impl IntoIterator<Item = String>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut text = format!("{header}\n");
+    for line in lines {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    fs::write(path, text)
+}
+
+/// Gets the current time in seconds since the unix epoch
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Formats seconds since the unix epoch as a `YYYY-MM-DD` date
+pub fn format_date(unix_time: u64) -> String {
+    // Days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (unix_time / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Formats a number of ticks as `m:ss.mmm`
+pub fn format_ticks(ticks: u64) -> String {
+    let millis = ticks * 1000 / TICKS_PER_SECOND as u64;
+    format!("{}:{:02}.{:03}", millis / 60000, millis / 1000 % 60, millis % 1000)
+}
+
+/// Formats a signed difference in ticks as `+s.mmm`, for comparing times
+pub fn format_delta(ticks: i64) -> String {
+    let sign = if ticks < 0 { '-' } else { '+' };
+    let millis = ticks.unsigned_abs() * 1000 / TICKS_PER_SECOND as u64;
+    format!("{sign}{}.{:03}", millis / 1000, millis % 1000)
+}
@@ -0,0 +1,381 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    game_state::{Coord, GameState},
+    json::Value,
+    movegen,
+    rotation_system::RotationSystem,
+    scoring::TSpin,
+    shapes::{Rotation, Shape},
+};
+
+/// How long a bot gets to introduce itself and accept the rules
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Rows of the board sent to bots, the protocol's board is taller than ours
+const BOARD_ROWS: usize = 40;
+
+/// Columns of the protocol's board
+const BOARD_COLUMNS: usize = 10;
+
+/// A placement in the protocol's coordinates
+///
+/// The protocol places pieces by their SRS rotation center, with x from the
+/// left and y up from the bottom row.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Move {
+    pub shape: Shape,
+    pub rotation: Rotation,
+    pub x: i16,
+    pub y: i16,
+    pub spin: TSpin,
+}
+
+impl Move {
+    /// Reads a move from a bot's suggestion
+    pub fn from_json(value: &Value) -> io::Result<Self> {
+        let location = value.get("location").ok_or_else(|| invalid("move has no location"))?;
+        let shape = location.get("type")
+            .and_then(Value::as_str)
+            .and_then(Shape::from_name)
+            .ok_or_else(|| invalid("move has an unknown piece"))?;
+        let rotation = match location.get("orientation").and_then(Value::as_str) {
+            Some("north") => Rotation::R0,
+            Some("east") => Rotation::R90,
+            Some("south") => Rotation::R180,
+            Some("west") => Rotation::R270,
+            _ => return Err(invalid("move has an unknown orientation")),
+        };
+        let coordinate = |name| {
+            location.get(name)
+                .and_then(Value::as_i64)
+                .and_then(|value| i16::try_from(value).ok())
+                .ok_or_else(|| invalid("move has an invalid location"))
+        };
+        let spin = match value.get("spin").and_then(Value::as_str) {
+            Some("mini") => TSpin::Mini,
+            Some("full") => TSpin::Full,
+            _ => TSpin::None,
+        };
+
+        Ok(Move { shape, rotation, x: coordinate("x")?, y: coordinate("y")?, spin })
+    }
+
+    /// Writes the move as the protocol sends it
+    pub fn to_json(&self) -> Value {
+        let orientation = match self.rotation {
+            Rotation::R0 => "north",
+            Rotation::R90 => "east",
+            Rotation::R180 => "south",
+            Rotation::R270 => "west",
+        };
+        let spin = match self.spin {
+            TSpin::None => "none",
+            TSpin::Mini => "mini",
+            TSpin::Full => "full",
+        };
+        let location = Value::object([
+            ("type", self.shape.name().into()),
+            ("orientation", orientation.into()),
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+        ]);
+        Value::object([("location", location), ("spin", spin.into())])
+    }
+
+    /// Gets where the top left of the piece's box is on a game's board
+    pub fn position(&self, game: &GameState) -> Coord {
+        let bottom = game.board.height() as i16 - 1;
+        let (box_x, box_y) = first_cell(&box_cells(self.shape, self.rotation));
+
+        // First cell of the piece on the board, flipping y to point down
+        let cells = center_cells(self.shape, self.rotation).map(|(dx, dy)| (self.x + dx, bottom - (self.y + dy)));
+        let (x, y) = first_cell(&cells);
+        Coord { x: x - box_x, y: y - box_y }
+    }
+}
+
+/// Gets the first cell in reading order, top to bottom then left to right
+fn first_cell(cells: &[(i16, i16)]) -> (i16, i16) {
+    *cells.iter().min_by_key(|(x, y)| (*y, *x)).unwrap()
+}
+
+/// Gets the cells of a piece in its box, y down
+fn box_cells(shape: Shape, rotation: Rotation) -> Vec<(i16, i16)> {
+    let cells = shape.get_shape(&rotation);
+    let mut found = vec![];
+    for (y, row) in cells.iter().enumerate() {
+        for (x, filled) in row.iter().enumerate() {
+            if *filled {
+                found.push((x as i16, y as i16));
+            }
+        }
+    }
+    found
+}
+
+/// Gets the cells of a piece around its rotation center, y up
+fn center_cells(shape: Shape, rotation: Rotation) -> [(i16, i16); 4] {
+    let north = match shape {
+        Shape::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        Shape::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        Shape::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        Shape::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        Shape::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+        Shape::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        Shape::Z => [(0, 0), (1, 0), (-1, 1), (0, 1)],
+    };
+    north.map(|(x, y)| match rotation {
+        Rotation::R0 => (x, y),
+        Rotation::R90 => (y, -x),
+        Rotation::R180 => (-x, -y),
+        Rotation::R270 => (-y, x),
+    })
+}
+
+/// An external bot speaking the Tetris Bot Protocol over its stdin and stdout
+///
+/// Keeps the bot's picture of the game in step with the moves it makes.
+/// Anything else that changes the game, like gravity locking a piece, needs
+/// a `restart`.
+pub struct TbpBot {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines the bot has written, read on another thread
+    messages: Receiver<String>,
+    pub name: String,
+    pub author: String,
+    /// Pieces the game had locked when the bot last caught up
+    pieces: u32,
+    /// Pieces the bot has been told about, counting the ones already drawn
+    revealed: usize,
+    /// Pieces drawn from the queue since the bot was started
+    drawn: usize,
+    /// Suggestions asked for in this game and not answered yet
+    asked: u32,
+    /// Suggestions asked for in earlier games, their answers are for other boards
+    stale: u32,
+}
+
+impl TbpBot {
+    /// Starts a bot program and agrees on the rules with it
+    pub fn spawn(program: &str, args: &[String]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Read lines as they come so waiting for the bot never blocks the game
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut bot = TbpBot {
+            child,
+            stdin,
+            messages,
+            name: String::new(),
+            author: String::new(),
+            pieces: 0,
+            revealed: 0,
+            drawn: 0,
+            asked: 0,
+            stale: 0,
+        };
+
+        // The bot speaks first
+        let info = bot.expect("info")?;
+        bot.name = info.get("name").and_then(Value::as_str).unwrap_or("bot").to_string();
+        bot.author = info.get("author").and_then(Value::as_str).unwrap_or_default().to_string();
+
+        bot.send(Value::object([("type", "rules".into())]))?;
+        bot.expect("ready")?;
+        Ok(bot)
+    }
+
+    /// Tells the bot the full state of a game to play from
+    pub fn start(&mut self, game: &GameState) -> io::Result<()> {
+        if game.board.width() != BOARD_COLUMNS || game.board.height() > BOARD_ROWS {
+            let msg = format!("bots only play on boards {BOARD_COLUMNS} wide and up to {BOARD_ROWS} rows");
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+        if game.rules.rotation_system != RotationSystem::Srs {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "bots only play with SRS rotation"));
+        }
+
+        // Rows from the bottom, empty above the board
+        let mut board: Vec<Value> = game.board.iter().rev()
+            .map(|row| Value::Array(row.iter().map(|color| color.letter().map(String::from).into()).collect()))
+            .collect();
+        board.resize(BOARD_ROWS, Value::Array(vec![Value::Null; BOARD_COLUMNS]));
+
+        // The queue starts with the piece being placed
+        let queue: Vec<&str> = [game.current_shape].iter().chain(&game.shape_queue).map(Shape::name).collect();
+
+        // The combo counts clears in a row, ours counts from 0 on the first
+        let combo = game.combo.map_or(0, |combo| combo + 1);
+
+        self.send(Value::object([
+            ("type", "start".into()),
+            ("hold", game.held.map(|shape| shape.name()).into()),
+            ("queue", queue.into()),
+            ("combo", combo.into()),
+            ("back_to_back", game.back_to_back.into()),
+            ("board", Value::Array(board)),
+        ]))?;
+        self.pieces = game.pieces;
+        self.revealed = game.shape_queue.len();
+        self.drawn = 0;
+        self.stale += std::mem::take(&mut self.asked);
+        Ok(())
+    }
+
+    /// Stops the bot's current game and starts it again from a game's state
+    pub fn restart(&mut self, game: &GameState) -> io::Result<()> {
+        self.send(Value::object([("type", "stop".into())]))?;
+        self.start(game)
+    }
+
+    /// Determines if the bot's picture of a game is still right
+    pub fn in_sync(&self, game: &GameState) -> bool {
+        game.pieces == self.pieces
+    }
+
+    /// Asks the bot for moves, answered through `poll_suggestion`
+    pub fn suggest(&mut self) -> io::Result<()> {
+        self.send(Value::object([("type", "suggest".into())]))?;
+        self.asked += 1;
+        Ok(())
+    }
+
+    /// Gets the bot's suggested moves, best first, if it has answered
+    ///
+    /// Bots answer suggestions in order, so answers to ones asked before the
+    /// game was last started are dropped.
+    pub fn poll_suggestion(&mut self) -> io::Result<Option<Vec<Move>>> {
+        loop {
+            let line = match self.messages.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err(io::Error::new(io::ErrorKind::BrokenPipe, "bot exited")),
+            };
+
+            // Other messages, like extensions this doesn't know, are skipped
+            let message = Value::parse(&line)?;
+            if message.get("type").and_then(Value::as_str) == Some("suggestion") {
+                if self.stale > 0 {
+                    self.stale -= 1;
+                    continue;
+                }
+                self.asked = self.asked.saturating_sub(1);
+                let moves = message.get("moves")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| invalid("suggestion has no moves"))?;
+                return moves.iter().map(Move::from_json).collect::<io::Result<_>>().map(Some);
+            }
+        }
+    }
+
+    /// Plays a move on a game and tells the bot, returning false if it can't be played
+    ///
+    /// A move of the held or next piece holds first.
+    pub fn play(&mut self, game: &mut GameState, mv: &Move) -> io::Result<bool> {
+        // Holding into an empty hold draws an extra piece
+        let drawn = if mv.shape != game.current_shape && game.held.is_none() { 2 } else { 1 };
+        if !apply(game, mv) {
+            return Ok(false);
+        }
+        self.send(Value::object([("type", "play".into()), ("move", mv.to_json())]))?;
+
+        self.drawn += drawn;
+        self.pieces = game.pieces;
+
+        // Tell the bot about the pieces that have come into view
+        let known = self.revealed.saturating_sub(self.drawn);
+        for i in known..game.shape_queue.len() {
+            let shape = game.shape_queue[i];
+            self.send(Value::object([("type", "new_piece".into()), ("piece", shape.name().into())]))?;
+        }
+        self.revealed = self.drawn + game.shape_queue.len();
+        Ok(true)
+    }
+
+    /// Sends a message as a line of json
+    fn send(&mut self, message: Value) -> io::Result<()> {
+        writeln!(self.stdin, "{message}")?;
+        self.stdin.flush()
+    }
+
+    /// Waits for a message of a type, failing on anything else
+    fn expect(&mut self, kind: &str) -> io::Result<Value> {
+        let line = match self.messages.recv_timeout(HANDSHAKE_TIMEOUT) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => return Err(io::Error::new(io::ErrorKind::TimedOut, format!("bot didn't send {kind}"))),
+            Err(RecvTimeoutError::Disconnected) => return Err(io::Error::new(io::ErrorKind::BrokenPipe, "bot exited")),
+        };
+
+        let message = Value::parse(&line)?;
+        match message.get("type").and_then(Value::as_str) {
+            Some(found) if found == kind => Ok(message),
+            Some("error") => {
+                let reason = message.get("reason").and_then(Value::as_str).unwrap_or("unknown");
+                Err(invalid(&format!("bot refused the game: {reason}")))
+            },
+            _ => Err(invalid(&format!("expected {kind} from the bot"))),
+        }
+    }
+}
+
+impl Drop for TbpBot {
+    /// Asks the bot to quit, then makes sure it has
+    fn drop(&mut self) {
+        let _ = self.send(Value::object([("type", "quit".into())]));
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Plays a move on a game, holding first if it is of another piece
+///
+/// Returns false without changing the game if the piece can't get there.
+pub fn apply(game: &mut GameState, mv: &Move) -> bool {
+    if mv.shape != game.current_shape {
+        let swap = game.held.or(game.shape_queue.first().copied());
+        if game.just_held || swap != Some(mv.shape) {
+            return false;
+        }
+
+        // Only hold once the move is known to be reachable from spawn
+        let pos = mv.position(game);
+        let reachable = movegen::placements(&game.board, game.rules.rotation_system, mv.shape, Rotation::R0, game.rules.spawn(&mv.shape))
+            .iter()
+            .any(|placement| placement.rotation == mv.rotation && placement.pos == pos);
+        if !reachable {
+            return false;
+        }
+        game.hold();
+    }
+
+    // The spin is the engine's to work out, whatever the bot says it is
+    game.place_at(mv.rotation, mv.position(game))
+}
+
+/// Creates an error for a message the bot shouldn't have sent
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
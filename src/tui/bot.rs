@@ -0,0 +1,192 @@
+use std::{io::{self, stdout, Stdout, Write}, thread::sleep, time::{Duration, Instant}};
+
+use crossterm::{cursor::MoveTo, event::KeyCode, style::Print, terminal::{self, Clear, ClearType}, QueueableCommand};
+use jordtris::{Bot, GamePhase, GameState, TbpBot, TICKS_PER_SECOND};
+
+use crate::{clean, draw, draw_box, frame_origin, frame_size, read_key_press, setup, Hud, Options, TickClock};
+
+/// Width of the status line under the board
+const STATUS_WIDTH: usize = 42;
+
+/// Who is playing
+pub enum Player {
+    /// The engine's own bot
+    Builtin(Bot),
+    /// An external bot through the Tetris Bot Protocol
+    External {
+        bot: TbpBot,
+        /// Set while waiting for the bot to suggest a move
+        thinking: bool,
+        /// Tick the bot can next be asked for a move on
+        next_move: u64,
+        /// Set once the bot has been sent a game, so later ones stop it first
+        started: bool,
+    },
+}
+
+impl Player {
+    /// Starts an external bot, exiting if it can't be
+    pub fn external(command: &[String]) -> Self {
+        match TbpBot::spawn(&command[0], &command[1..]) {
+            Ok(bot) => Player::External { bot, thinking: false, next_move: 0, started: false },
+            Err(err) => {
+                eprintln!("error: could not start bot {}: {err}", command[0]);
+                std::process::exit(1);
+            },
+        }
+    }
+
+    /// Gets the name shown under the board
+    fn name(&self) -> String {
+        match self {
+            Player::Builtin(_) => "jordtris".to_string(),
+            Player::External { bot, .. } if bot.author.is_empty() => bot.name.clone(),
+            Player::External { bot, .. } => format!("{} by {}", bot.name, bot.author),
+        }
+    }
+
+    /// Gets the player going on a new game
+    fn start(&mut self, game: &GameState) -> Result<(), io::Error> {
+        match self {
+            Player::Builtin(bot) => bot.reset(),
+            Player::External { bot, thinking, next_move, started } => {
+                if *started {
+                    bot.restart(game)?;
+                } else {
+                    bot.start(game)?;
+                    *started = true;
+                }
+                *thinking = false;
+                *next_move = 0;
+            },
+        }
+        Ok(())
+    }
+
+    /// Advances the game by the ticks due, letting the player move
+    fn update(&mut self, game: &mut GameState, ticks: u32, pps: f64) -> Result<(), io::Error> {
+        match self {
+            Player::Builtin(bot) => {
+                for _ in 0..ticks {
+                    let inputs = bot.think(game);
+                    game.step(&inputs);
+                }
+            },
+            Player::External { bot, thinking, next_move, .. } => {
+                for _ in 0..ticks {
+                    game.step(&[]);
+                }
+
+                // A piece locked by gravity leaves the bot behind
+                if !bot.in_sync(game) {
+                    bot.restart(game)?;
+                    *thinking = false;
+                }
+
+                if !*thinking && game.tick >= *next_move {
+                    bot.suggest()?;
+                    *thinking = true;
+                }
+                if *thinking && let Some(moves) = bot.poll_suggestion()? {
+                    *thinking = false;
+                    *next_move = game.tick + (TICKS_PER_SECOND as f64 / pps).round() as u64;
+
+                    // Moves are tried best first, if none fit the bot has lost track of the game
+                    let mut played = false;
+                    for mv in &moves {
+                        if bot.play(game, mv)? {
+                            played = true;
+                            break;
+                        }
+                    }
+                    if !played && game.game_phase == GamePhase::Playing {
+                        bot.restart(game)?;
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Watches a bot play, no faster than the pieces per second option
+///
+/// Gravity still runs, so a slow external bot can be locked out of a piece.
+pub fn run(options: &Options, mut player: Player) -> Result<(), io::Error> {
+    setup();
+    let frame_time = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut out = stdout();
+    let mut game = options.new_game();
+    let mut hud = Hud::for_game(&game);
+    let mut clock = TickClock::new();
+    let mut previous_frame: Vec<String> = vec![];
+    player.start(&game)?;
+
+    out.queue(Clear(ClearType::All))?;
+    loop {
+        // Get current time
+        let start = Instant::now();
+
+        if game.game_phase == GamePhase::GameOver {
+            let lines = [
+                String::new(),
+                format!("Score {}  Lines {}", game.score, game.lines),
+                String::new(),
+                "R to restart, Esc to quit".to_string(),
+            ];
+            draw_box(&mut out, "GAME OVER", &lines)?;
+
+            while let Some(evt) = read_key_press()? {
+                match evt.code {
+                    KeyCode::Char('r') => {
+                        game = options.new_game();
+                        hud = Hud::for_game(&game);
+                        player.start(&game)?;
+                        previous_frame.clear();
+                        out.queue(Clear(ClearType::All))?;
+                        clock.reset();
+                        break;
+                    },
+                    KeyCode::Esc | KeyCode::Char('q') => clean(),
+                    _ => {},
+                }
+            }
+        } else {
+            while let Some(evt) = read_key_press()? {
+                if let KeyCode::Esc | KeyCode::Char('q') = evt.code {
+                    clean();
+                }
+            }
+
+            player.update(&mut game, clock.ticks_due(), options.bot_pps)?;
+            hud.handle_events(&mut game);
+            draw(&mut out, &game, &hud, &mut previous_frame)?;
+            draw_status(&mut out, &player, &game, options.bot_pps)?;
+            if game.game_phase == GamePhase::GameOver {
+                out.queue(Clear(ClearType::All))?;
+            }
+        }
+
+        // Wait for frame
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            sleep(frame_time - elapsed);
+        }
+    }
+}
+
+/// Draws who is playing under the board
+fn draw_status(out: &mut Stdout, player: &Player, game: &GameState, pps: f64) -> Result<(), io::Error> {
+    let lines = [
+        format!("BOT {}", player.name()),
+        format!("{} pieces at up to {pps} a second  Q quit", game.pieces),
+    ];
+
+    let (left, top) = frame_origin(terminal::size()?, game);
+    let (_, height) = frame_size(game);
+    for (i, line) in lines.iter().enumerate() {
+        out.queue(MoveTo(left, top + height + i as u16))?;
+        out.queue(Print(format!("{line:<STATUS_WIDTH$}")))?;
+    }
+    out.flush()
+}
@@ -0,0 +1,169 @@
+use std::{io::{self, stdout, Stdout, Write}, thread::sleep, time::{Duration, Instant}};
+
+use crossterm::{event::{poll, read, Event, KeyCode, KeyModifiers}, style::StyledContent, terminal::{self, Clear, ClearType}, QueueableCommand};
+use jordtris::{GameState, Input, Outcome, Session, TICKS_PER_SECOND};
+
+use crate::{board_columns, clean, draw_box, draw_frame, frame_origin, frame_size, garbage_meter_tile, read_key_press, render_frame, setup, ColorTile, Hud, Keyboard, TickClock};
+
+/// Columns between the local panel and the opponent's board
+const PANEL_GAP: u16 = 4;
+
+/// Plays a networked match until either player leaves
+///
+/// The local board is drawn in full with the opponent's board shrunk beside it.
+pub fn run(mut session: Session) -> Result<(), io::Error> {
+    setup();
+    let frame_time = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut out = stdout();
+    let mut clock = TickClock::new();
+    let mut keyboard = Keyboard::new();
+    let mut inputs: Vec<Input> = vec![];
+    let mut huds = [Hud::for_game(&session.games[0]), Hud::new()];
+    let mut previous_frames = [vec![], vec![]];
+    let mut result = None;
+
+    out.queue(Clear(ClearType::All))?;
+    loop {
+        // Get current time
+        let start = Instant::now();
+
+        if let Some(title) = result {
+            let lines = [
+                String::new(),
+                "Esc to quit".to_string(),
+                String::new(),
+            ];
+            draw_box(&mut out, title, &lines)?;
+
+            while let Some(evt) = read_key_press()? {
+                if evt.code == KeyCode::Esc {
+                    session.quit();
+                    clean();
+                }
+            }
+        } else {
+            read_inputs(&mut session, &mut keyboard, &mut inputs)?;
+
+            // Ticks the opponent is too far behind for are skipped, slowing both down
+            for _ in 0..clock.ticks_due() {
+                if session.send_inputs(&inputs)? {
+                    inputs.clear();
+                }
+            }
+            session.receive()?;
+            while session.step() {}
+            for (hud, game) in huds.iter_mut().zip(session.games.iter_mut()) {
+                hud.handle_events(game);
+            }
+            draw_boards(&mut out, &session, &huds, &mut previous_frames)?;
+
+            result = match Outcome::of([&session.games[0], &session.games[1]]) {
+                Some(outcome) => Some(outcome_title(outcome)),
+                None if session.opponent_left => Some("OPPONENT LEFT"),
+                None => None,
+            };
+            if result.is_some() {
+                out.queue(Clear(ClearType::All))?;
+            }
+        }
+
+        // Wait for frame
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            sleep(frame_time - elapsed);
+        }
+    }
+}
+
+/// Reads key events into the local inputs, there is no pausing a networked game
+fn read_inputs(session: &mut Session, keyboard: &mut Keyboard, inputs: &mut Vec<Input>) -> Result<(), io::Error> {
+    while poll(Duration::from_secs(0))? {
+        if let Event::Key(evt) = read()? {
+            // Control + c or Esc leaves the match
+            if (evt.code == KeyCode::Char('c') && evt.modifiers.contains(KeyModifiers::CONTROL))
+                || (evt.code == KeyCode::Esc && !evt.kind.is_release())
+            {
+                session.quit();
+                clean();
+            }
+
+            keyboard.handle(evt, inputs);
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws the local board in full and the opponent's board shrunk to its right
+fn draw_boards(out: &mut Stdout, session: &Session, huds: &[Hud; 2], previous_frames: &mut [Vec<String>; 2]) -> Result<(), io::Error> {
+    let size = terminal::size()?;
+    let game = &session.games[0];
+    let (panel_width, _) = frame_size(game);
+    let width = panel_width + PANEL_GAP + mini_columns(game) as u16;
+    let left = (size.0 / 2).saturating_sub(width / 2);
+    let (_, top) = frame_origin(size, game);
+
+    let mut frames = render_frame(game, &huds[0]);
+    frames[0] = format!("{:^1$}", "YOU", board_columns(game));
+    draw_frame(out, &frames, (left, top), &mut previous_frames[0])?;
+
+    let frames = render_mini_frame(&session.games[1]);
+    draw_frame(out, &frames, (left + panel_width + PANEL_GAP, top), &mut previous_frames[1])?;
+
+    out.flush()
+}
+
+/// Builds the lines of a small board with only its score below
+fn render_mini_frame(game: &GameState) -> Vec<String> {
+    let (width, hidden, visible) = (game.board.width(), game.rules.board.hidden_rows, game.rules.board.visible_rows());
+    let mut frames: Vec<String> = vec![String::new(); visible + 5];
+    frames[0] = format!("{:^1$}", "OPPONENT", mini_columns(game));
+    frames[1] = format!("┌{}┐", "─".repeat(width));
+
+    let shape = game.shape_cells(game.current_shape, game.rotation);
+    for y in hidden..game.board.height() {
+        let frame = &mut frames[y - hidden + 2];
+        frame.push_str(&garbage_meter_tile(game, y));
+        for x in 0..width {
+            // Ghosts don't show at this size
+            let dx = x as i16 - game.player_pos.x;
+            let dy = y as i16 - game.player_pos.y;
+            let color = if game.board[y][x].is_block() {
+                game.board[y][x]
+            } else if (0..4).contains(&dx) && (0..4).contains(&dy) && shape[dy as usize][dx as usize] {
+                game.current_shape.get_color()
+            } else {
+                frame.push(' ');
+                continue;
+            };
+            frame.push_str(&mini_tile(color.color_tile()).to_string());
+        }
+        frame.push('│');
+    }
+
+    frames[visible + 2] = format!("└{}┘", "─".repeat(width));
+    let columns = mini_columns(game).max(12);
+    frames[visible + 3] = format!("{:<5}{:>2$}", "SCORE", game.score, columns - 5);
+    frames[visible + 4] = format!("{:<5}{:>2$}", "LINES", game.lines, columns - 5);
+    frames
+}
+
+/// Gets the columns taken by a small board, one a cell and the edges
+fn mini_columns(game: &GameState) -> usize {
+    game.board.width() + 2
+}
+
+/// Narrows a board tile to a single column, keeping its style
+fn mini_tile(tile: StyledContent<&'static str>) -> StyledContent<&'static str> {
+    let content = if tile.content().starts_with('▓') { "▓" } else { "█" };
+    StyledContent::new(*tile.style(), content)
+}
+
+/// Gets the title of the result screen
+fn outcome_title(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Win(0) => "YOU WIN",
+        Outcome::Win(_) => "YOU LOSE",
+        Outcome::Draw => "DRAW",
+    }
+}
@@ -0,0 +1,120 @@
+use std::{io::{self, stdout, Stdout, Write}, path::Path, thread::sleep, time::{Duration, Instant}};
+
+use crossterm::{cursor::MoveTo, event::KeyCode, style::Print, terminal, QueueableCommand};
+use jordtris::{storage, Replay, ReplayPlayer, TICKS_PER_SECOND};
+
+use crate::{clean, draw, frame_origin, frame_size, read_key_press, setup, Hud, TickClock};
+
+/// Playback speeds cycled through with F
+const SPEEDS: [u32; 4] = [1, 2, 4, 8];
+
+/// Ticks jumped by a single seek
+const SEEK_TICKS: u64 = 5 * TICKS_PER_SECOND as u64;
+
+/// Width of the status line under the board
+const STATUS_WIDTH: usize = 42;
+
+/// Plays a replay file back with pause, frame step, fast forward and seek controls
+pub fn run(path: &Path) -> Result<(), io::Error> {
+    // Load before touching the terminal so errors stay readable
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("error: could not load {}: {err}", path.display());
+            std::process::exit(1);
+        },
+    };
+
+    setup();
+    let frame_time = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut out = stdout();
+    let mut player = ReplayPlayer::new(replay);
+    let mut clock = TickClock::new();
+    let mut hud = Hud::new();
+    let mut previous_frame: Vec<String> = vec![];
+    let mut paused = false;
+    let mut speed = 0;
+
+    loop {
+        // Get current time
+        let start = Instant::now();
+
+        // Controls
+        while let Some(evt) = read_key_press()? {
+            let seek_to = match evt.code {
+                KeyCode::Char(' ') => {
+                    paused = !paused;
+                    None
+                },
+                KeyCode::Char('.') if paused => Some(player.game.tick + 1),
+                KeyCode::Char('f') => {
+                    speed = (speed + 1) % SPEEDS.len();
+                    None
+                },
+                KeyCode::Left => Some(player.game.tick.saturating_sub(SEEK_TICKS)),
+                KeyCode::Right => Some(player.game.tick + SEEK_TICKS),
+                KeyCode::Home | KeyCode::Char('0') => Some(0),
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    clean();
+                    None
+                },
+                _ => None,
+            };
+
+            // Going backwards replays from the start, so old events are stale
+            if let Some(tick) = seek_to {
+                if tick < player.game.tick {
+                    hud = Hud::new();
+                }
+                player.seek(tick);
+            }
+        }
+
+        // Advance playback
+        let ticks = clock.ticks_due();
+        if !paused {
+            for _ in 0..ticks * SPEEDS[speed] {
+                player.step();
+            }
+        }
+        hud.handle_events(&mut player.game);
+
+        // Draw
+        draw(&mut out, &player.game, &hud, &mut previous_frame)?;
+        draw_status(&mut out, &player, paused, SPEEDS[speed])?;
+
+        // Wait for frame
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            sleep(frame_time - elapsed);
+        }
+    }
+}
+
+/// Draws the playback position and controls under the board
+fn draw_status(out: &mut Stdout, player: &ReplayPlayer, paused: bool, speed: u32) -> Result<(), io::Error> {
+    let state = if player.is_finished() {
+        "END".to_string()
+    } else if paused {
+        "PAUSED".to_string()
+    } else {
+        format!("{speed}x")
+    };
+    let lines = [
+        format!(
+            "REPLAY {} / {}  {}",
+            storage::format_ticks(player.game.tick),
+            storage::format_ticks(player.replay.length),
+            state,
+        ),
+        "Space pause  . step  F speed  ←/→ seek  Q quit".to_string(),
+    ];
+
+    let (left, top) = frame_origin(terminal::size()?, &player.game);
+    let (_, height) = frame_size(&player.game);
+    for (i, line) in lines.iter().enumerate() {
+        out.queue(MoveTo(left, top + height + i as u16))?;
+        out.queue(Print(format!("{line:<STATUS_WIDTH$}")))?;
+    }
+    out.flush()
+}
@@ -0,0 +1,248 @@
+use std::{io::{self, stdout, Stdout, Write}, thread::sleep, time::{Duration, Instant}};
+
+use crossterm::{event::{poll, read, Event, KeyCode, KeyModifiers}, terminal::{self, Clear, ClearType}, QueueableCommand};
+use jordtris::{Bot, Button, GameEvent, GamePhase, GameState, Input, Outcome, TICKS_PER_SECOND};
+
+use crate::{board_columns, clean, draw_box, draw_frame, frame_origin, frame_size, read_key_press, render_frame, setup, Hud, Keyboard, Options, TickClock, KEY_BINDINGS, PAUSE_KEYS};
+
+/// Keys for the player on the left, around WASD
+const LEFT_BINDINGS: [(KeyCode, Button); 7] = [
+    (KeyCode::Char('a'), Button::Left),
+    (KeyCode::Char('d'), Button::Right),
+    (KeyCode::Char('s'), Button::SoftDrop),
+    (KeyCode::Char('w'), Button::HardDrop),
+    (KeyCode::Char('e'), Button::RotateCw),
+    (KeyCode::Char('q'), Button::RotateCcw),
+    (KeyCode::Tab, Button::Hold),
+];
+
+/// Keys for the player on the right, around the arrows
+const RIGHT_BINDINGS: [(KeyCode, Button); 7] = [
+    (KeyCode::Left, Button::Left),
+    (KeyCode::Right, Button::Right),
+    (KeyCode::Down, Button::SoftDrop),
+    (KeyCode::Enter, Button::HardDrop),
+    (KeyCode::Up, Button::RotateCw),
+    (KeyCode::Char('.'), Button::RotateCcw),
+    (KeyCode::Char('/'), Button::Hold),
+];
+
+/// Columns between the two panels
+const PANEL_GAP: u16 = 4;
+
+/// One side of a versus match
+struct Player {
+    game: GameState,
+    hud: Hud,
+    keyboard: Keyboard,
+    /// Plays instead of the keyboard when set
+    bot: Option<Bot>,
+    /// Inputs waiting for the next tick, including garbage from the opponent
+    inputs: Vec<Input>,
+    previous_frame: Vec<String>,
+}
+
+impl Player {
+    /// Creates a player about to start a game
+    fn new(game: GameState, bindings: &'static [(KeyCode, Button)], bot: Option<Bot>) -> Self {
+        Player {
+            hud: Hud::new(),
+            game,
+            keyboard: Keyboard::with_bindings(bindings),
+            bot,
+            inputs: vec![],
+            previous_frame: vec![],
+        }
+    }
+}
+
+/// Plays local versus matches between two players on one keyboard, or one against the bot
+///
+/// Both boards get the same pieces, and every attack is sent to the other board.
+pub fn run(options: &Options, vs_bot: bool) -> Result<(), io::Error> {
+    setup();
+    let frame_time = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut out = stdout();
+    let mut players = new_match(options, vs_bot);
+    let mut clock = TickClock::new();
+    let mut wins = [0; 2];
+    let mut outcome = None;
+
+    out.queue(Clear(ClearType::All))?;
+    loop {
+        // Get current time
+        let start = Instant::now();
+
+        if let Some(result) = outcome.map(|outcome| outcome_title(outcome, &players)) {
+            // Result screen until a rematch is asked for, other keys are likely leftover drops
+            let lines = [
+                String::new(),
+                format!("P1  {} - {}  {}", wins[0], wins[1], if vs_bot { "CPU" } else { "P2" }),
+                String::new(),
+                "R for a rematch, Esc to quit".to_string(),
+            ];
+            draw_box(&mut out, &result, &lines)?;
+
+            while let Some(evt) = read_key_press()? {
+                match evt.code {
+                    KeyCode::Char('r') => {
+                        players = new_match(options, vs_bot);
+                        outcome = None;
+                        out.queue(Clear(ClearType::All))?;
+                        clock.reset();
+                        break;
+                    },
+                    KeyCode::Esc => clean(),
+                    _ => {},
+                }
+            }
+        } else if players[0].game.game_phase == GamePhase::Paused {
+            let lines = [
+                String::new(),
+                "Esc / P to resume, Q to quit".to_string(),
+                String::new(),
+            ];
+            draw_box(&mut out, "PAUSED", &lines)?;
+
+            while let Some(evt) = read_key_press()? {
+                if PAUSE_KEYS.contains(&evt.code) {
+                    resume(&mut players, &mut out)?;
+                    clock.reset();
+                    break;
+                } else if evt.code == KeyCode::Char('q') {
+                    clean();
+                }
+            }
+        } else {
+            read_inputs(&mut players)?;
+            for _ in 0..clock.ticks_due() {
+                step(&mut players);
+            }
+            draw_players(&mut out, &mut players, &wins)?;
+
+            outcome = Outcome::of([&players[0].game, &players[1].game]);
+            if let Some(Outcome::Win(player)) = outcome {
+                wins[player] += 1;
+            }
+            if outcome.is_some() {
+                out.queue(Clear(ClearType::All))?;
+            }
+        }
+
+        // Wait for frame
+        let elapsed = start.elapsed();
+        if elapsed < frame_time {
+            sleep(frame_time - elapsed);
+        }
+    }
+}
+
+/// Creates both players for a new match, sharing a seed so they get the same pieces
+///
+/// Against the bot the one player gets the usual keys.
+fn new_match(options: &Options, vs_bot: bool) -> [Player; 2] {
+    let game = options.new_game();
+    let other = GameState::with_rules(game.seed, game.rules.clone());
+    if vs_bot {
+        return [Player::new(game, &KEY_BINDINGS, None), Player::new(other, &[], Some(Bot::new(options.bot_pps)))];
+    }
+    [Player::new(game, &LEFT_BINDINGS, None), Player::new(other, &RIGHT_BINDINGS, None)]
+}
+
+/// Reads key events into each player's inputs
+fn read_inputs(players: &mut [Player; 2]) -> Result<(), io::Error> {
+    while poll(Duration::from_secs(0))? {
+        if let Event::Key(evt) = read()? {
+            // Control + c
+            if evt.code == KeyCode::Char('c')
+                && evt.modifiers.contains(KeyModifiers::CONTROL)
+            {
+                clean(); // Clean and exit game
+            }
+
+            // Either player can pause both games
+            if PAUSE_KEYS.contains(&evt.code) && !evt.kind.is_release() {
+                for player in players.iter_mut() {
+                    player.game.pause();
+                }
+                return Ok(());
+            }
+
+            // Each player only sees their own keys
+            for player in players.iter_mut() {
+                player.keyboard.handle(evt, &mut player.inputs);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Advances both games by a tick, sending each attack to the other player
+fn step(players: &mut [Player; 2]) {
+    for player in players.iter_mut() {
+        if let Some(bot) = &mut player.bot {
+            let inputs = bot.think(&player.game);
+            player.inputs.extend(inputs);
+        }
+        player.game.step(&player.inputs);
+        player.inputs.clear();
+    }
+
+    // Garbage arrives on the opponent's next tick
+    for i in 0..2 {
+        for event in players[i].hud.handle_events(&mut players[i].game) {
+            if let GameEvent::Attack(lines) = event {
+                players[1 - i].inputs.push(Input::Garbage(lines));
+            }
+        }
+    }
+}
+
+/// Resumes both games with fresh keyboards and a clean screen
+fn resume(players: &mut [Player; 2], out: &mut Stdout) -> Result<(), io::Error> {
+    out.queue(Clear(ClearType::All))?;
+    for player in players.iter_mut() {
+        player.game.resume();
+        player.keyboard = Keyboard::with_bindings(player.keyboard.bindings);
+        player.inputs.retain(|input| matches!(input, Input::Garbage(_))); // Keep garbage in flight
+        player.previous_frame.clear();
+    }
+    Ok(())
+}
+
+/// Draws both boards side by side, each labelled with its player
+fn draw_players(out: &mut Stdout, players: &mut [Player; 2], wins: &[u32; 2]) -> Result<(), io::Error> {
+    let size = terminal::size()?;
+    let (panel_width, _) = frame_size(&players[0].game);
+    let width = 2 * panel_width + PANEL_GAP;
+    let left = (size.0 / 2).saturating_sub(width / 2);
+    let (_, top) = frame_origin(size, &players[0].game);
+
+    for (i, player) in players.iter_mut().enumerate() {
+        let mut frames = render_frame(&player.game, &player.hud);
+        let name = format!("{}  {}", player_name(player, i), wins[i]);
+        frames[0] = format!("{name:^0$}", board_columns(&player.game));
+
+        let x = left + i as u16 * (panel_width + PANEL_GAP);
+        draw_frame(out, &frames, (x, top), &mut player.previous_frame)?;
+    }
+
+    out.flush()
+}
+
+/// Gets the title of the result screen
+fn outcome_title(outcome: Outcome, players: &[Player; 2]) -> String {
+    match outcome {
+        Outcome::Win(player) => format!("{} WINS", player_name(&players[player], player)),
+        Outcome::Draw => "DRAW".to_string(),
+    }
+}
+
+/// Gets the name a player is labelled with
+fn player_name(player: &Player, i: usize) -> String {
+    match player.bot {
+        Some(_) => "CPU".to_string(),
+        None => format!("PLAYER {}", i + 1),
+    }
+}
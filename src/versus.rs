@@ -0,0 +1,44 @@
+use std::cmp::Ordering;
+
+use crate::game_state::{GamePhase, GameState};
+
+/// How a match between two boards ended
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The board at this index won
+    Win(usize),
+
+    /// Both boards finished on the same tick with the same result, and the
+    /// same score if the mode is ranked by score
+    Draw,
+}
+
+impl Outcome {
+    /// Determines how a match has ended, if it has
+    ///
+    /// The match ends as soon as either game does. Reaching the mode's goal
+    /// wins, topping out loses. Boards reaching the goal together in a mode
+    /// ranked by score, like ultra running out of time, go on score.
+    pub fn of(games: [&GameState; 2]) -> Option<Self> {
+        let over = games.map(|game| game.game_phase == GamePhase::GameOver);
+        let completed = games.map(|game| game.completed);
+
+        if over == [false, false] {
+            return None;
+        }
+        if over == [true, true] && completed[0] == completed[1] {
+            let scores = games.map(|game| game.score);
+            let by_score = completed[0] && !games[0].rules.mode.ranks_by_time();
+            return Some(match scores[0].cmp(&scores[1]) {
+                Ordering::Greater if by_score => Outcome::Win(0),
+                Ordering::Less if by_score => Outcome::Win(1),
+                _ => Outcome::Draw,
+            });
+        }
+
+        // Whoever reached the goal wins, otherwise whoever is still playing
+        (0..2).find(|i| completed[*i])
+            .or_else(|| (0..2).find(|i| !over[*i]))
+            .map(Outcome::Win)
+    }
+}
@@ -0,0 +1,21 @@
+//! Checks received garbage doesn't get counted as dig mode's own
+
+use jordtris::{Button, GameMode, GameState, Input, Ruleset};
+
+#[test]
+fn received_garbage_isnt_dug() {
+    let rules = Ruleset { mode: GameMode::DIG, ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    game.step(&[Input::Garbage(4)]);
+
+    // Wait out the garbage delay, then lock a piece without clearing so it rises
+    for _ in 0..game.rules.garbage_delay {
+        game.step(&[]);
+    }
+    game.step(&[Input::Press(Button::HardDrop)]);
+
+    assert_eq!(game.incoming_garbage(), 0);
+    assert_eq!(game.garbage_rows(), 10);
+    assert_eq!(game.garbage_dug(), 0);
+    assert!(!game.goal_reached());
+}
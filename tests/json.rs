@@ -0,0 +1,42 @@
+//! Checks the JSON parser follows the grammar and round trips what it writes
+
+use jordtris::json::Value;
+
+#[test]
+fn numbers_follow_the_grammar() {
+    for (text, expected) in [("0", 0.0), ("-0", 0.0), ("12", 12.0), ("-3.25", -3.25), ("1e3", 1000.0), ("2E-2", 0.02), ("0.5e+1", 5.0)] {
+        assert_eq!(Value::parse(text).unwrap(), Value::Number(expected), "{text}");
+    }
+
+    for text in ["+1", "1.", ".5", "01", "-", "1e", "1e+", "1+5", "1.2.3", "--1", "0x10", "1e5e5"] {
+        assert!(Value::parse(text).is_err(), "{text}");
+    }
+}
+
+#[test]
+fn values_round_trip() {
+    let text = r#"{"type":"start","hold":null,"queue":["T","I"],"combo":3,"back_to_back":true,"name":"a \"bot\"\n"}"#;
+    let value = Value::parse(text).unwrap();
+    assert_eq!(value.get("type").and_then(Value::as_str), Some("start"));
+    assert_eq!(value.get("combo").and_then(Value::as_i64), Some(3));
+    assert_eq!(value.get("hold"), Some(&Value::Null));
+    assert_eq!(value.get("name").and_then(Value::as_str), Some("a \"bot\"\n"));
+    assert_eq!(Value::parse(&value.to_string()).unwrap(), value);
+}
+
+#[test]
+fn whitespace_and_escapes() {
+    let value = Value::parse(" [ 1 , \"\\u00e9\\ud83d\\ude00\" , { } ] ").unwrap();
+    assert_eq!(value, Value::Array(vec![Value::Number(1.0), Value::String("é😀".to_string()), Value::Object(vec![])]));
+}
+
+#[test]
+fn malformed_documents_are_refused() {
+    for text in ["", "[1,]", "{\"a\"}", "{\"a\":1,}", "\"open", "\"\\x\"", "\"\\ud83d\"", "tru", "[1] 2", "nul"] {
+        assert!(Value::parse(text).is_err(), "{text:?}");
+    }
+
+    // Deep nesting is an error rather than a stack overflow
+    let deep = "[".repeat(1000) + &"]".repeat(1000);
+    assert!(Value::parse(&deep).is_err());
+}
@@ -0,0 +1,50 @@
+//! Checks levels go up with lines and gravity follows the guideline curve
+
+use jordtris::{rules::{MAX_GRAVITY, ONE_G}, Coord, GameState, Gravity, Rotation, Ruleset, Shape, ShapeColor, TICKS_PER_SECOND};
+
+#[test]
+fn guideline_gravity_follows_the_formula() {
+    for level in 1..19 {
+        let seconds = (0.8 - (level - 1) as f64 * 0.007).powi(level as i32 - 1);
+        let expected = ONE_G as f64 / (seconds * TICKS_PER_SECOND as f64);
+        let gravity = Gravity::Guideline.for_level(level);
+        assert!((gravity as f64 - expected).abs() <= 1.0, "level {level} has {gravity}, expected {expected}");
+    }
+
+    // 20G from level 19 on
+    assert_eq!(Gravity::Guideline.for_level(19), MAX_GRAVITY);
+    assert_eq!(Gravity::Guideline.for_level(99), MAX_GRAVITY);
+}
+
+#[test]
+fn other_gravities() {
+    assert_eq!(Gravity::Fixed(ONE_G).for_level(30), ONE_G);
+    assert_eq!(Gravity::Fixed(u32::MAX).for_level(1), MAX_GRAVITY);
+
+    let table = Gravity::Table(vec![100, 200, 300]);
+    assert_eq!(table.for_level(1), 100);
+    assert_eq!(table.for_level(3), 300);
+    assert_eq!(table.for_level(10), 300);
+}
+
+#[test]
+fn levels_go_up_every_few_lines() {
+    let rules = Ruleset { start_level: 3, lines_per_level: 3, ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    assert_eq!(game.level, 3);
+    let bottom = game.board.height() - 1;
+
+    // A tetris for 4 lines goes up one level, another for 8 goes up two
+    for (lines, level) in [(4, 4), (8, 5)] {
+        for y in bottom - 3..=bottom {
+            for x in 1..game.board.width() {
+                game.board.set(x, y, ShapeColor::Garbage);
+            }
+        }
+        game.current_shape = Shape::I;
+        game.rotation = Rotation::R0;
+        game.player_pos = game.rules.spawn(&Shape::I);
+        assert!(game.place_at(Rotation::R90, Coord { x: -2, y: bottom as i16 - 3 }));
+        assert_eq!((game.lines, game.level), (lines, level));
+    }
+}
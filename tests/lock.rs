@@ -0,0 +1,75 @@
+//! Checks how each lock mode resets the lock timer
+
+use jordtris::{rules::MAX_GRAVITY, Button, GameState, Gravity, Input, LockMode, Ruleset};
+
+/// Starts a 20G game, so the piece is grounded from the first tick
+fn grounded(lock_mode: LockMode) -> GameState {
+    let rules = Ruleset { gravity: Gravity::Fixed(MAX_GRAVITY), lock_mode, ..Ruleset::default() };
+    GameState::with_rules(7, rules)
+}
+
+/// Taps a direction, moving a column without auto shifting
+fn tap(game: &mut GameState, button: Button) {
+    game.step(&[Input::Press(button), Input::Release(button)]);
+}
+
+/// Rocks the piece left and right every few ticks, until it locks or the ticks run out
+fn rock(game: &mut GameState, every: u32, ticks: u32) {
+    for tick in 1..=ticks {
+        if game.pieces > 0 {
+            return;
+        }
+        match tick % (2 * every) {
+            0 => tap(game, Button::Left),
+            n if n == every => tap(game, Button::Right),
+            _ => game.step(&[]),
+        }
+    }
+}
+
+#[test]
+fn extended_runs_out_of_resets() {
+    let mut game = grounded(LockMode::Extended { max_resets: 3 });
+    game.step(&[]);
+
+    // Each move starts the timer over until the last reset, which locks at once
+    tap(&mut game, Button::Left);
+    tap(&mut game, Button::Right);
+    assert_eq!((game.move_resets, game.pieces), (2, 0));
+    tap(&mut game, Button::Left);
+    assert_eq!(game.pieces, 1);
+}
+
+#[test]
+fn extended_resets_come_back_on_a_new_lowest_row() {
+    let rules = Ruleset { gravity: Gravity::Fixed(0), lock_mode: LockMode::GUIDELINE, ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    let lowest = game.lowest_row;
+
+    tap(&mut game, Button::Left);
+    tap(&mut game, Button::Right);
+    assert_eq!(game.move_resets, 2);
+
+    tap(&mut game, Button::SoftDrop);
+    assert_eq!(game.lowest_row, lowest + 1);
+    assert_eq!(game.move_resets, 0);
+}
+
+#[test]
+fn infinite_never_locks_while_moving() {
+    let mut game = grounded(LockMode::Infinite);
+    rock(&mut game, 10, 600);
+    assert_eq!(game.pieces, 0);
+}
+
+#[test]
+fn step_reset_and_classic_ignore_moves() {
+    for lock_mode in [LockMode::StepReset, LockMode::Classic] {
+        let mut game = grounded(lock_mode);
+        let delay = game.rules.lock_delay;
+        rock(&mut game, 10, delay - 1);
+        assert_eq!(game.pieces, 0, "{lock_mode:?}");
+        rock(&mut game, 10, 1);
+        assert_eq!(game.pieces, 1, "{lock_mode:?}");
+    }
+}
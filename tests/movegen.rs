@@ -0,0 +1,48 @@
+//! Checks the move generator lists each resting place once
+
+use std::collections::HashSet;
+
+use jordtris::{movegen, Board, Rotation, Ruleset, Shape, ShapeColor, TSpin};
+
+/// Builds a board from rows of `#` and `.`, the last row at the bottom
+fn board(rows: &[&str]) -> Board {
+    let mut board = Board::default();
+    let top = board.height() - rows.len();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.chars().enumerate() {
+            if cell == '#' {
+                board.set(x, top + y, ShapeColor::Garbage);
+            }
+        }
+    }
+    board
+}
+
+#[test]
+fn empty_board_placements_are_distinct() {
+    let rules = Ruleset::default();
+    for shape in [Shape::I, Shape::J, Shape::L, Shape::O, Shape::Z, Shape::T, Shape::S] {
+        let found = movegen::placements(&Board::default(), rules.rotation_system, shape, Rotation::R0, rules.spawn(&shape));
+        let distinct: HashSet<_> = found.iter().map(|placement| (placement.pos.x, placement.pos.y, placement.rotation)).collect();
+        assert_eq!(found.len(), distinct.len(), "{} has repeats", shape.name());
+    }
+}
+
+#[test]
+fn t_slot_placement_keeps_the_spin() {
+    let rules = Ruleset::default();
+    let board = board(&[
+        "####......",
+        "###...####",
+        "####.#####",
+    ]);
+    let found = movegen::placements(&board, rules.rotation_system, Shape::T, Rotation::R0, rules.spawn(&Shape::T));
+    let distinct: HashSet<_> = found.iter().map(|placement| (placement.pos.x, placement.pos.y, placement.rotation)).collect();
+    assert_eq!(found.len(), distinct.len());
+
+    // Pointing down into the slot is only reached by spinning in
+    let slot = found.iter()
+        .find(|placement| placement.rotation == Rotation::R180 && (placement.pos.x, placement.pos.y) == (3, 19))
+        .unwrap();
+    assert_eq!(slot.spin, TSpin::Full);
+}
@@ -0,0 +1,97 @@
+//! Plays networked matches over localhost and checks both ends agree
+
+use std::{net::TcpListener, thread, time::{Duration, Instant}};
+
+use jordtris::{netplay::Message, Bot, Button, GameMode, Input, Ruleset, Session};
+
+/// Ticks each end plays for
+const TICKS: u64 = 3600;
+
+/// Plays a session with a bot on the local board, returning it once every tick has run
+fn play(mut session: Session) -> Session {
+    let mut bot = Bot::new(10.0);
+    let mut ticks = 0;
+    while ticks < TICKS {
+        session.receive().unwrap();
+        let inputs = bot.think(&session.games[0]);
+        while !session.send_inputs(&inputs).unwrap() {
+            session.receive().unwrap();
+            if session.step() {
+                ticks += 1;
+            }
+        }
+        while ticks < TICKS && session.step() {
+            ticks += 1;
+        }
+        thread::yield_now();
+    }
+    session
+}
+
+#[test]
+fn both_ends_see_the_same_boards() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let host = thread::spawn(move || play(Session::host(&listener, 7, Ruleset::default()).unwrap()));
+    let joiner = play(Session::join(addr).unwrap());
+    let host = host.join().unwrap();
+
+    // Each end keeps its own board first
+    for (mine, theirs) in [(&host.games[0], &joiner.games[1]), (&host.games[1], &joiner.games[0])] {
+        assert_eq!(mine.tick, theirs.tick);
+        assert_eq!(mine.board, theirs.board);
+        assert_eq!(mine.score, theirs.score);
+        assert_eq!(mine.input_log, theirs.input_log);
+    }
+
+    // Garbage has to have been sent both ways for the timing to be tested
+    for game in &host.games {
+        assert!(game.input_log.iter().any(|(_, input)| matches!(input, Input::Garbage(_))));
+    }
+}
+
+#[test]
+fn joiner_plays_the_hosts_game() {
+    let rules = Ruleset { mode: GameMode::Sprint { lines: 20 }, start_level: 5, ..Ruleset::default() };
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let host = thread::spawn(move || Session::host(&listener, 42, rules).unwrap());
+    let joiner = Session::join(addr).unwrap();
+    let host = host.join().unwrap();
+
+    for game in host.games.iter().chain(&joiner.games) {
+        assert_eq!(game.seed, 42);
+        assert_eq!(game.rules, host.games[0].rules);
+        assert_eq!(game.shape_queue, host.games[0].shape_queue);
+    }
+}
+
+#[test]
+fn quitting_tells_the_opponent() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let host = thread::spawn(move || Session::host(&listener, 7, Ruleset::default()).unwrap());
+    let mut joiner = Session::join(addr).unwrap();
+    let mut host = host.join().unwrap();
+
+    host.quit();
+    let start = Instant::now();
+    while !joiner.opponent_left {
+        assert!(start.elapsed() < Duration::from_secs(5), "the bye never arrived");
+        joiner.receive().unwrap();
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn messages_round_trip() {
+    let messages = [
+        Message::Hello { seed: u64::MAX, rules: Ruleset { mode: GameMode::Ultra { seconds: 60 }, ..Ruleset::default() } },
+        Message::Inputs { tick: 300, inputs: vec![Input::Press(Button::Left), Input::Release(Button::Left), Input::Garbage(4)] },
+        Message::Inputs { tick: 0, inputs: vec![] },
+        Message::Bye,
+    ];
+    for message in messages {
+        assert_eq!(Message::decode(&message.encode()).unwrap(), message);
+    }
+}
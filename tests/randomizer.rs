@@ -0,0 +1,67 @@
+//! Checks every randomizer follows its seed and draws the way it should
+
+use jordtris::{randomizer::PieceGenerator, Randomizer, Shape};
+
+/// Draws pieces from a randomizer
+fn draw(randomizer: Randomizer, seed: u64, count: usize) -> Vec<Shape> {
+    let mut generator = PieceGenerator::new(randomizer, seed);
+    (0..count).map(|_| generator.next_shape()).collect()
+}
+
+/// Every piece
+const SHAPES: [Shape; 7] = [Shape::I, Shape::J, Shape::L, Shape::O, Shape::Z, Shape::T, Shape::S];
+
+/// Counts each piece, in the order of `SHAPES`
+fn counts(shapes: &[Shape]) -> [usize; 7] {
+    SHAPES.map(|shape| shapes.iter().filter(|drawn| **drawn == shape).count())
+}
+
+#[test]
+fn seeds_give_the_same_pieces() {
+    for randomizer in Randomizer::ALL {
+        assert_eq!(draw(randomizer, 7, 500), draw(randomizer, 7, 500), "{}", randomizer.name());
+        assert_ne!(draw(randomizer, 7, 500), draw(randomizer, 8, 500), "{}", randomizer.name());
+    }
+}
+
+#[test]
+fn bags_hold_every_piece() {
+    // The 7-bag's first piece comes before its first bag
+    let shapes = draw(Randomizer::SevenBag, 7, 1 + 7 * 50);
+    for bag in shapes[1..].chunks(7) {
+        assert_eq!(counts(bag), [1; 7]);
+    }
+
+    for bag in draw(Randomizer::FourteenBag, 7, 14 * 50).chunks(14) {
+        assert_eq!(counts(bag), [2; 7]);
+    }
+
+    for bag in draw(Randomizer::BagPlusOne, 7, 8 * 50).chunks(8) {
+        assert!(counts(bag).iter().all(|count| *count >= 1));
+    }
+}
+
+#[test]
+fn nes_rerolls_repeats_once() {
+    // A repeat needs a repeat or reroll, then the reroll to repeat, 1 in 28
+    let shapes = draw(Randomizer::Nes, 7, 100_000);
+    let repeats = shapes.windows(2).filter(|pair| pair[0] == pair[1]).count();
+    let rate = repeats as f64 / shapes.len() as f64;
+    assert!((rate - 1.0 / 28.0).abs() < 0.005, "repeat rate {rate}");
+}
+
+#[test]
+fn tgm_avoids_its_history() {
+    // Never an S, Z or O first
+    for seed in 0..200 {
+        let first = draw(Randomizer::Tgm, seed, 1)[0];
+        assert!(![Shape::S, Shape::Z, Shape::O].contains(&first));
+    }
+
+    // A piece among the last 4 needs the roll and all 6 rerolls to hit the
+    // history, about (4/7)^7 or 2%, where one reroll fewer would be 3.5%
+    let shapes = draw(Randomizer::Tgm, 7, 100_000);
+    let repeats = (4..shapes.len()).filter(|i| shapes[i - 4..*i].contains(&shapes[*i])).count();
+    let rate = repeats as f64 / shapes.len() as f64;
+    assert!(rate < 0.025, "history repeat rate {rate}");
+}
@@ -0,0 +1,34 @@
+//! Checks replays keep the rules they were played with and play back the same
+
+use jordtris::{Button, GameState, Handling, Input, Replay, ReplayPlayer, Ruleset};
+
+#[test]
+fn soft_drop_factors_round_trip() {
+    for soft_drop_factor in [None, Some(0), Some(1), Some(20)] {
+        let rules = Ruleset { handling: Handling { soft_drop_factor, ..Handling::default() }, ..Ruleset::default() };
+        let replay = Replay::from_game(&GameState::with_rules(7, rules));
+        assert_eq!(Replay::decode(&replay.encode()).unwrap(), replay);
+    }
+}
+
+#[test]
+fn paused_games_replay_the_same() {
+    let mut game = GameState::with_rules(7, Ruleset::default());
+
+    // Pause partway through charging auto shift, then drop once resumed
+    game.step(&[Input::Press(Button::Left)]);
+    for _ in 0..5 {
+        game.step(&[]);
+    }
+    game.pause();
+    game.resume();
+    for _ in 0..30 {
+        game.step(&[]);
+    }
+    game.step(&[Input::Press(Button::HardDrop)]);
+
+    let mut player = ReplayPlayer::new(Replay::from_game(&game));
+    player.seek(game.tick);
+    assert_eq!(player.game.tick, game.tick);
+    assert_eq!(player.game.board, game.board);
+}
@@ -0,0 +1,89 @@
+//! Checks the kicks and states of each rotation system
+
+use jordtris::{Board, Coord, Rotation, RotationSystem, Shape, ShapeColor};
+
+const ROTATIONS: [Rotation; 4] = [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270];
+const SHAPES: [Shape; 7] = [Shape::I, Shape::J, Shape::L, Shape::O, Shape::Z, Shape::T, Shape::S];
+
+/// Every turn a piece can make, clockwise and counter clockwise
+fn turns() -> impl Iterator<Item = (Rotation, Rotation)> {
+    ROTATIONS.into_iter().flat_map(|from| [(from, from.rotate_cw()), (from, from.rotate_ccw())])
+}
+
+#[test]
+fn srs_plus_only_changes_the_i() {
+    for shape in SHAPES.into_iter().filter(|shape| *shape != Shape::I) {
+        for (from, to) in turns() {
+            assert_eq!(RotationSystem::SrsPlus.kicks(shape, from, to), RotationSystem::Srs.kicks(shape, from, to));
+        }
+    }
+
+    // Like TETR.IO, the I's first kick is the same way round each side
+    let kicks = |from, to| RotationSystem::SrsPlus.kicks(Shape::I, from, to);
+    assert_eq!(kicks(Rotation::R0, Rotation::R90), &[(0, 0), (1, 0), (-2, 0), (-2, -1), (1, 2)]);
+    assert_eq!(kicks(Rotation::R0, Rotation::R270), &[(0, 0), (-1, 0), (2, 0), (2, -1), (-1, 2)]);
+    assert_eq!(kicks(Rotation::R180, Rotation::R90), &[(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)]);
+    assert_eq!(kicks(Rotation::R180, Rotation::R270), &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)]);
+}
+
+#[test]
+fn nrs_and_no_kicks_rotate_in_place() {
+    for system in [RotationSystem::Nrs, RotationSystem::NoKicks] {
+        for shape in SHAPES {
+            for (from, to) in turns() {
+                assert_eq!(system.kicks(shape, from, to), &[(0, 0)]);
+            }
+        }
+    }
+}
+
+#[test]
+fn ars_kicks_a_column_either_way_but_not_the_i() {
+    for shape in SHAPES {
+        for (from, to) in turns() {
+            let expected: &[(i16, i16)] = match shape {
+                Shape::I | Shape::O => &[(0, 0)],
+                _ => &[(0, 0), (1, 0), (-1, 0)],
+            };
+            assert_eq!(RotationSystem::Ars.kicks(shape, from, to), expected);
+        }
+    }
+}
+
+#[test]
+fn ars_and_nrs_spawn_pointing_down() {
+    let srs_down = RotationSystem::Srs.cells(Shape::T, Rotation::R180);
+    assert_eq!(RotationSystem::Nrs.cells(Shape::T, Rotation::R0), srs_down);
+    assert_eq!(RotationSystem::Ars.cells(Shape::T, Rotation::R0), srs_down);
+
+    // Both only have two I states
+    for system in [RotationSystem::Ars, RotationSystem::Nrs] {
+        assert_eq!(system.cells(Shape::I, Rotation::R0), system.cells(Shape::I, Rotation::R180));
+        assert_eq!(system.cells(Shape::I, Rotation::R90), system.cells(Shape::I, Rotation::R270));
+    }
+}
+
+#[test]
+fn ars_centre_column_rule() {
+    let pos = Coord { x: 3, y: 10 };
+    let cells = RotationSystem::Ars.cells(Shape::T, Rotation::R90);
+    let rotate = |board: &Board| RotationSystem::Ars.rotate(board, Shape::T, Rotation::R0, Rotation::R90, &pos);
+
+    // The first target cell in the way, reading along the rows, picks the rule
+    let blocking: Vec<(usize, usize)> = (0..4)
+        .flat_map(|dy| (0..4).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| cells[*dy][*dx])
+        .collect();
+
+    // Blocked in the centre column, no kicks are tried
+    let (dx, dy) = blocking.iter().find(|(dx, _)| *dx == 1).copied().unwrap();
+    let mut board = Board::default();
+    board.set(pos.x as usize + dx, pos.y as usize + dy, ShapeColor::Garbage);
+    assert_eq!(rotate(&board), None);
+
+    // Blocked first beside it, the kick right is taken
+    let (dx, dy) = blocking.iter().find(|(dx, _)| *dx != 1).copied().unwrap();
+    let mut board = Board::default();
+    board.set(pos.x as usize + dx, pos.y as usize + dy, ShapeColor::Garbage);
+    assert_eq!(rotate(&board), Some((Coord { x: pos.x + 1, y: pos.y }, 1)));
+}
@@ -0,0 +1,88 @@
+//! Checks guideline points, bonuses and attack for line clears
+
+use jordtris::{AttackTable, ClearScore, Coord, GameEvent, GameState, Rotation, Ruleset, Shape, ShapeColor, TSpin};
+
+#[test]
+fn clears_scale_with_level() {
+    let points = [(1, TSpin::None, 100), (2, TSpin::None, 300), (3, TSpin::None, 500), (4, TSpin::None, 800),
+        (0, TSpin::Mini, 100), (1, TSpin::Mini, 200), (0, TSpin::Full, 400), (1, TSpin::Full, 800),
+        (2, TSpin::Full, 1200), (3, TSpin::Full, 1600)];
+    for (lines, tspin, base) in points {
+        assert_eq!(ClearScore::new(lines, tspin, 1, 0, false, false).total(), base);
+        assert_eq!(ClearScore::new(lines, tspin, 3, 0, false, false).total(), 3 * base);
+    }
+}
+
+#[test]
+fn back_to_back_only_follows_difficult_clears() {
+    let tetris = ClearScore::new(4, TSpin::None, 1, 0, true, false);
+    assert!(tetris.back_to_back);
+    assert_eq!(tetris.back_to_back_bonus, 400);
+    assert_eq!(tetris.total(), 1200);
+
+    let tspin_single = ClearScore::new(1, TSpin::Full, 2, 0, true, false);
+    assert_eq!(tspin_single.back_to_back_bonus, 800);
+
+    // A plain triple or a spin without lines neither gets nor keeps the bonus
+    assert!(!ClearScore::new(3, TSpin::None, 1, 0, true, false).back_to_back);
+    assert!(!ClearScore::new(0, TSpin::Full, 1, 0, true, false).back_to_back);
+}
+
+#[test]
+fn combos_and_perfect_clears_add_on() {
+    assert_eq!(ClearScore::new(1, TSpin::None, 2, 3, false, false).combo_bonus, 300);
+    assert_eq!(ClearScore::new(1, TSpin::None, 1, 0, false, true).perfect_clear_bonus, 800);
+    assert_eq!(ClearScore::new(4, TSpin::None, 1, 0, false, true).perfect_clear_bonus, 2000);
+    assert_eq!(ClearScore::new(4, TSpin::None, 2, 0, true, true).perfect_clear_bonus, 6400);
+}
+
+#[test]
+fn attack_tables() {
+    let attack = |table: AttackTable, lines, tspin, combo, back_to_back, perfect_clear| {
+        table.attack(&ClearScore::new(lines, tspin, 1, combo, back_to_back, perfect_clear))
+    };
+    assert_eq!(attack(AttackTable::Guideline, 1, TSpin::None, 0, false, false), 0);
+    assert_eq!(attack(AttackTable::Guideline, 4, TSpin::None, 0, false, false), 4);
+    assert_eq!(attack(AttackTable::Guideline, 4, TSpin::None, 0, true, false), 5);
+    assert_eq!(attack(AttackTable::Guideline, 2, TSpin::Full, 0, false, false), 4);
+    assert_eq!(attack(AttackTable::Guideline, 2, TSpin::Mini, 0, false, false), 1);
+    assert_eq!(attack(AttackTable::Guideline, 1, TSpin::None, 4, false, false), 2);
+    assert_eq!(attack(AttackTable::Guideline, 1, TSpin::None, 20, false, false), 5);
+    assert_eq!(attack(AttackTable::Guideline, 1, TSpin::None, 0, false, true), 10);
+
+    // Classic only counts lines
+    assert_eq!(attack(AttackTable::Classic, 2, TSpin::Full, 5, true, true), 1);
+    assert_eq!(attack(AttackTable::Classic, 4, TSpin::None, 0, true, false), 4);
+}
+
+#[test]
+fn tetrises_in_a_row_combo_and_chain() {
+    let mut game = GameState::with_rules(7, Ruleset::default());
+    let bottom = game.board.height() - 1;
+
+    // Two wells of four rows, each filled by a vertical I
+    for y in bottom - 7..=bottom {
+        for x in 1..game.board.width() {
+            game.board.set(x, y, ShapeColor::Garbage);
+        }
+    }
+
+    let mut clears = vec![];
+    for _ in 0..2 {
+        game.current_shape = Shape::I;
+        game.rotation = Rotation::R0;
+        game.player_pos = game.rules.spawn(&Shape::I);
+        let pos = Coord { x: -2, y: bottom as i16 - 3 };
+        assert!(game.place_at(Rotation::R90, pos));
+        clears.extend(game.take_events().into_iter().filter_map(|event| match event {
+            GameEvent::Clear(clear) => Some(clear),
+            _ => None,
+        }));
+    }
+
+    assert_eq!(clears.len(), 2);
+    assert_eq!((clears[0].combo, clears[0].back_to_back), (0, false));
+    assert_eq!((clears[1].combo, clears[1].back_to_back), (1, true));
+    assert!(clears[1].perfect_clear);
+    assert_eq!(game.score, clears.iter().map(ClearScore::total).sum::<u32>());
+}
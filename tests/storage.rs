@@ -0,0 +1,45 @@
+//! Checks the score files round trip and refuse files in another format
+
+use std::{env, fs, path::PathBuf};
+
+use jordtris::{GameState, HighScore, HighScores, PersonalBests, Ruleset};
+
+/// A fresh path in the temp directory, so tests don't share files
+fn temp_file(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("jordtris-test-{}", std::process::id()));
+    let path = dir.join(name);
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn high_scores_round_trip() {
+    let path = temp_file("highscores.tsv");
+    assert!(HighScores::load(&path).unwrap().top("Marathon").is_empty());
+
+    let game = GameState::with_rules(7, Ruleset::default());
+    let mut scores = HighScores::default();
+    scores.insert(HighScore::from_game("ann", &game));
+    scores.save(&path).unwrap();
+
+    let loaded = HighScores::load(&path).unwrap();
+    assert_eq!(loaded, scores);
+    assert!(fs::read_to_string(&path).unwrap().starts_with("# jordtris highscores v1\n"));
+}
+
+#[test]
+fn files_without_the_header_are_refused() {
+    let path = temp_file("foreign.tsv");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    for text in ["name\tscore\nann\t100\n", "# jordtris highscores v0\n", "# jordtris personal bests v1\n"] {
+        fs::write(&path, text).unwrap();
+        assert!(HighScores::load(&path).is_err(), "{text:?}");
+    }
+    fs::write(&path, "# jordtris highscores v1\n").unwrap();
+    assert!(PersonalBests::load(&path).is_err());
+
+    // An empty file is an empty table
+    fs::write(&path, "").unwrap();
+    assert!(HighScores::load(&path).is_ok());
+}
@@ -0,0 +1,33 @@
+//! Checks bots' moves are only played where the piece can get to
+
+use jordtris::{tbp, GameState, Rotation, Ruleset, Shape, ShapeColor, TSpin};
+
+/// Starts a game with the given piece at spawn
+fn game_with(shape: Shape) -> GameState {
+    let mut game = GameState::with_rules(7, Ruleset::default());
+    game.current_shape = shape;
+    game.rotation = Rotation::R0;
+    game.player_pos = game.rules.spawn(&shape);
+    game
+}
+
+#[test]
+fn enclosed_cavity_is_unreachable() {
+    let mut game = game_with(Shape::O);
+
+    // Bury a 2x2 hole under a full roof
+    let bottom = game.board.height() - 1;
+    for y in bottom - 2..=bottom {
+        for x in 0..game.board.width() {
+            if y == bottom - 2 || !(4..6).contains(&x) {
+                game.board.set(x, y, ShapeColor::Garbage);
+            }
+        }
+    }
+    let board = game.board.clone();
+
+    let mv = tbp::Move { shape: Shape::O, rotation: Rotation::R0, x: 4, y: 0, spin: TSpin::None };
+    assert!(!tbp::apply(&mut game, &mv));
+    assert_eq!(game.board, board);
+    assert_eq!(game.pieces, 0);
+}
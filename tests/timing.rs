@@ -0,0 +1,145 @@
+//! Checks gravity, soft drop, lock delay and auto shift tick by tick
+
+use jordtris::{rules::{MAX_GRAVITY, ONE_G}, BoardSize, Button, Coord, Direction, GamePhase, GameState, Gravity, Handling, Input, Rotation, Ruleset, Shape};
+
+/// Steps a game without inputs
+fn wait(game: &mut GameState, ticks: u32) {
+    for _ in 0..ticks {
+        game.step(&[]);
+    }
+}
+
+#[test]
+fn gravity_builds_up_over_ticks() {
+    let rules = Ruleset { gravity: Gravity::Fixed(ONE_G / 4), ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    let y = game.player_pos.y;
+
+    // A quarter of a row a tick falls on every fourth tick
+    wait(&mut game, 3);
+    assert_eq!(game.player_pos.y, y);
+    wait(&mut game, 1);
+    assert_eq!(game.player_pos.y, y + 1);
+    wait(&mut game, 4);
+    assert_eq!(game.player_pos.y, y + 2);
+}
+
+#[test]
+fn grounded_piece_locks_after_the_delay() {
+    let rules = Ruleset { gravity: Gravity::Fixed(MAX_GRAVITY), ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    let delay = game.rules.lock_delay;
+
+    // 20G lands on the first tick, which counts towards the delay
+    wait(&mut game, 1);
+    assert!(game.is_grounded());
+    wait(&mut game, delay - 2);
+    assert_eq!(game.pieces, 0);
+    wait(&mut game, 1);
+    assert_eq!(game.pieces, 1);
+}
+
+#[test]
+fn held_direction_repeats_after_das() {
+    let rules = Ruleset { gravity: Gravity::Fixed(0), ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    let Handling { das, arr, .. } = game.rules.handling;
+    let x = game.player_pos.x;
+
+    // The press moves once, then nothing until das ticks have passed
+    game.step(&[Input::Press(Button::Left)]);
+    assert_eq!(game.player_pos.x, x - 1);
+    wait(&mut game, das - 1);
+    assert_eq!(game.player_pos.x, x - 1);
+    wait(&mut game, 1);
+    assert_eq!(game.player_pos.x, x - 2);
+
+    // Then once every arr ticks
+    wait(&mut game, arr - 1);
+    assert_eq!(game.player_pos.x, x - 2);
+    wait(&mut game, 1);
+    assert_eq!(game.player_pos.x, x - 3);
+}
+
+#[test]
+fn instant_arr_slides_to_the_wall() {
+    let handling = Handling { arr: 0, ..Handling::default() };
+    let rules = Ruleset { gravity: Gravity::Fixed(0), handling, ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    let x = game.player_pos.x;
+
+    game.step(&[Input::Press(Button::Left)]);
+    wait(&mut game, handling.das - 1);
+    assert_eq!(game.player_pos.x, x - 1);
+    wait(&mut game, 1);
+    assert!(!game.move_player_horizontal(Direction::Left));
+}
+
+#[test]
+fn nothing_falls_after_topping_out() {
+    let rules = Ruleset { gravity: Gravity::Fixed(ONE_G), ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+
+    // Block the top left cell of the next piece's spawn so it tops out
+    let next = game.shape_queue[0];
+    let spawn = game.rules.spawn(&next);
+    let cells = game.rules.rotation_system.cells(next, Rotation::R0);
+    let (dx, dy) = (0..4)
+        .flat_map(|dy| (0..4).map(move |dx| (dx, dy)))
+        .find(|(dx, dy)| cells[*dy][*dx])
+        .unwrap();
+    game.board.set(spawn.x as usize + dx, spawn.y as usize + dy, next.get_color());
+
+    // The spawned piece stays put though there is room below it
+    game.step(&[Input::Press(Button::HardDrop)]);
+    assert_eq!(game.game_phase, GamePhase::GameOver);
+    assert_eq!(game.current_shape, next);
+    assert_eq!(game.player_pos, spawn);
+}
+
+#[test]
+fn topping_out_leaves_the_board_alone() {
+    let board = BoardSize { width: BoardSize::MIN_WIDTH, ..BoardSize::default() };
+    let mut game = GameState::with_rules(7, Ruleset { board, ..Ruleset::default() });
+
+    // A piece hanging off the right edge can't be placed, and isn't written
+    game.current_shape = Shape::T;
+    game.rotation = Rotation::R0;
+    game.player_pos = Coord { x: 2, y: 10 };
+    let before = game.board.clone();
+    game.place_and_reset();
+    assert_eq!(game.game_phase, GamePhase::GameOver);
+    assert_eq!((game.board, game.pieces), (before, 0));
+}
+
+#[test]
+fn soft_drop_multiplies_gravity() {
+    let handling = Handling { soft_drop_factor: Some(4), ..Handling::default() };
+    let rules = Ruleset { gravity: Gravity::Fixed(ONE_G / 4), handling, ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+    let y = game.player_pos.y;
+
+    // The press moves a row straight away, then gravity runs 4 times as fast
+    game.step(&[Input::Press(Button::SoftDrop)]);
+    assert_eq!(game.player_pos.y, y + 2);
+    wait(&mut game, 3);
+    assert_eq!(game.player_pos.y, y + 5);
+
+    // Letting go goes back to a row every 4 ticks
+    game.step(&[Input::Release(Button::SoftDrop)]);
+    wait(&mut game, 2);
+    assert_eq!(game.player_pos.y, y + 5);
+    wait(&mut game, 1);
+    assert_eq!(game.player_pos.y, y + 6);
+}
+
+#[test]
+fn instant_soft_drop_reaches_the_floor_on_the_press() {
+    let handling = Handling { soft_drop_factor: None, ..Handling::default() };
+    let rules = Ruleset { gravity: Gravity::Fixed(0), handling, ..Ruleset::default() };
+    let mut game = GameState::with_rules(7, rules);
+
+    game.step(&[Input::Press(Button::SoftDrop)]);
+    assert!(game.is_grounded());
+    assert_eq!(game.pieces, 0);
+}
@@ -0,0 +1,55 @@
+//! Checks T-spins are classified by the 3 corner rule
+
+use jordtris::{Coord, GameState, Rotation, Ruleset, Shape, ShapeColor, TSpin};
+
+/// Rests the current piece at a spot as if the given kick just rotated it there
+fn rest(game: &mut GameState, shape: Shape, rotation: Rotation, pos: Coord, kick: Option<usize>) -> TSpin {
+    game.current_shape = shape;
+    game.rotation = rotation;
+    game.player_pos = pos;
+    game.last_kick = kick;
+    game.tspin()
+}
+
+#[test]
+fn slot_with_both_front_corners_is_full() {
+    let mut game = GameState::with_rules(7, Ruleset::default());
+    let bottom = game.board.height() as i16 - 1;
+
+    // A T-spin double slot, pointing down with the overhang on the left
+    for (x, y) in [(3, bottom - 2), (3, bottom - 1), (3, bottom), (5, bottom)] {
+        game.board.set(x, y as usize, ShapeColor::Garbage);
+    }
+    let pos = Coord { x: 3, y: bottom - 2 };
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R180, pos.clone(), Some(0)), TSpin::Full);
+
+    // Only rotating into place spins, and only the T
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R180, pos.clone(), None), TSpin::None);
+    assert_eq!(rest(&mut game, Shape::J, Rotation::R180, pos, Some(0)), TSpin::None);
+}
+
+#[test]
+fn one_front_corner_is_a_mini_unless_the_last_kick() {
+    let mut game = GameState::with_rules(7, Ruleset::default());
+    let bottom = game.board.height() as i16 - 1;
+
+    // Pointing up on the floor, the floor fills both back corners
+    let pos = Coord { x: 3, y: bottom - 1 };
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R0, pos.clone(), Some(0)), TSpin::None);
+
+    game.board.set(3, bottom as usize - 1, ShapeColor::Garbage);
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R0, pos.clone(), Some(0)), TSpin::Mini);
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R0, pos.clone(), Some(3)), TSpin::Mini);
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R0, pos, Some(4)), TSpin::Full);
+}
+
+#[test]
+fn walls_count_as_corners() {
+    let mut game = GameState::with_rules(7, Ruleset::default());
+    let bottom = game.board.height() as i16 - 1;
+
+    // Pointing right against the left wall, on a block under its point
+    game.board.set(1, bottom as usize, ShapeColor::Garbage);
+    let pos = Coord { x: -1, y: bottom - 2 };
+    assert_eq!(rest(&mut game, Shape::T, Rotation::R90, pos, Some(0)), TSpin::Mini);
+}
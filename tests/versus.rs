@@ -0,0 +1,26 @@
+//! Checks how versus matches that time out together are decided
+
+use jordtris::{Button, GameMode, GameState, Input, Outcome, Ruleset};
+
+/// Plays ultra boards until time runs out, hard dropping the given number of pieces on each
+fn ultra(drops: [u32; 2]) -> Option<Outcome> {
+    let rules = Ruleset { mode: GameMode::Ultra { seconds: 1 }, ..Ruleset::default() };
+    let mut games = [GameState::with_rules(7, rules.clone()), GameState::with_rules(7, rules)];
+    for (game, drops) in games.iter_mut().zip(drops) {
+        for _ in 0..drops {
+            game.step(&[Input::Press(Button::HardDrop)]);
+        }
+        while !game.completed {
+            game.step(&[]);
+        }
+    }
+    assert_eq!(games[0].tick, games[1].tick);
+    Outcome::of([&games[0], &games[1]])
+}
+
+#[test]
+fn timed_out_boards_go_on_score() {
+    assert_eq!(ultra([2, 1]), Some(Outcome::Win(0)));
+    assert_eq!(ultra([1, 2]), Some(Outcome::Win(1)));
+    assert_eq!(ultra([1, 1]), Some(Outcome::Draw));
+}